@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Once};
 
 pub use crate::attacks::Attacks;
 use crate::{attacks::Ray, bitboard::BitBoard, Color, PieceType, Square};
@@ -35,6 +35,8 @@ pub static mut RAYS: [[BB12<Square12>; 144]; 8] = [[BB12::new(0, 0); 144]; 8];
 static mut BETWEEN_BB: [[BB12<Square12>; 144]; 144] =
     [[BB12::new(0, 0); 144]; 144];
 
+static INIT: Once = Once::new();
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Attacks12<S, B>
 where
@@ -84,6 +86,10 @@ impl Attacks<Square12, BB12<Square12>> for Attacks12<Square12, BB12<Square12>> {
 
     fn init_quick() {}
 
+    fn init() {
+        INIT.call_once(Self::init_tables);
+    }
+
     fn init_north_ray() {
         let empty = BB12::empty();
         for sq in 0..144 {
@@ -302,6 +308,10 @@ impl Attacks<Square12, BB12<Square12>> for Attacks12<Square12, BB12<Square12>> {
         unsafe { BETWEEN_BB[square1.index()][square2.index()] }
     }
 
+    fn ray(dir: Ray, sq: &Square12) -> BB12<Square12> {
+        unsafe { RAYS[dir as usize][sq.index()] }
+    }
+
     fn get_pawn_moves(square: usize, color: Color) -> BB12<Square12> {
         match color {
             Color::White | Color::Black => unsafe {
@@ -528,6 +538,32 @@ mod tests2 {
         }
     }
 
+    #[test]
+    fn ray() {
+        Attacks12::init();
+        assert_eq!(Attacks12::ray(Ray::North, &A1).len(), 11);
+        assert!(Attacks12::ray(Ray::West, &A1).is_empty());
+        let beyond = Attacks12::ray(Ray::NorthEast, &C3);
+        assert_eq!(beyond.len(), 9);
+        assert!((beyond & &D4).is_any());
+        assert!((beyond & &C3).is_empty());
+    }
+
+    #[test]
+    fn init_from_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    Attacks12::init();
+                    Attacks12::between(A1, A12).len()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 10);
+        }
+    }
+
     #[test]
     fn giraffe_attacks() {
         let cases = [(&G5, vec![&F9, &H9, &C6, &K6, &C4, &K4, &F1, &H1])];
@@ -538,4 +574,19 @@ mod tests2 {
             }
         }
     }
+
+    #[test]
+    fn lame_giraffe_attacks() {
+        Attacks12::init();
+        let mut blockers = EMPTY_BB;
+        for sq in [&H6, &F7, &J4] {
+            blockers |= sq;
+        }
+        let leaper = Attacks12::get_giraffe_attacks_with(&G5, blockers, false);
+        assert_eq!(leaper.len(), 8);
+        let lame = Attacks12::get_giraffe_attacks_with(&G5, blockers, true);
+        let mut lame: Vec<_> = lame.collect();
+        lame.sort_by_key(|sq| sq.index());
+        assert_eq!(lame, vec![F1, H1, C4, C6]);
+    }
 }
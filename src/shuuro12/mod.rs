@@ -5,3 +5,5 @@ pub mod plinths_set12;
 pub mod position12;
 pub mod shop;
 pub mod square12;
+
+pub use board_defs::{FILE_BB, RANK_BB};
@@ -1,8 +1,12 @@
 use crate::{
-    attacks::Attacks, bitboard::BitBoard, plinths_set::PlinthGen, Square,
+    attacks::Attacks,
+    bitboard::BitBoard,
+    plinths_set::{PlinthGen, PlinthRng},
+    Square,
 };
 
 use super::{attacks12::Attacks12, bitboard12::BB12, square12::Square12};
+use rand::{thread_rng, Rng};
 use std::marker::PhantomData;
 
 pub struct PlinthGen12<S, B>
@@ -33,13 +37,18 @@ impl PlinthGen<Square12, BB12<Square12>>
 
 impl PlinthGen12<Square12, BB12<Square12>> {
     pub fn start(&self) -> BB12<Square12> {
+        self.start_with(&mut PlinthRng::new(thread_rng().gen()))
+    }
+
+    /// Generate plinths using given random number generator.
+    pub fn start_with(&self, rng: &mut PlinthRng) -> BB12<Square12> {
         let sections = [
             (0, 6, 0, 6, 2),
             (0, 6, 6, 12, 2),
             (6, 12, 0, 6, 2),
             (6, 12, 6, 12, 2),
         ];
-        self.generate_plinths(&sections)
+        self.generate_plinths(&sections, rng)
     }
 }
 
@@ -1,9 +1,13 @@
 use std::{fmt, marker::PhantomData};
 
+#[cfg(feature = "serde")]
+use crate::position::PositionData;
 use crate::{
     bitboard::BitBoard,
-    position::{Board, Outcome, Placement, Play, Position, Rules, Sfen},
-    Color, Hand, Move, MoveData, Piece, PieceType, SfenError, Square, Variant,
+    plinths_set::PlinthRng,
+    position::{Board, Clock, Outcome, Placement, Play, Position, Rules, Sfen},
+    zobrist, Color, Hand, Move, MoveData, Piece, PieceType, SfenError, Square,
+    Variant,
 };
 
 use super::{
@@ -34,11 +38,43 @@ where
     color_bb: [BB12<Square12>; 3],
     game_status: Outcome,
     variant: Variant,
+    auto_draw_repetition: bool,
+    auto_draw_material: bool,
+    halfmove_clock: u16,
+    record_history: bool,
+    zobrist: u64,
+    clock: Option<Clock>,
     pub type_bb: [BB12<Square12>; 10],
     _a: PhantomData<B>,
     _s: PhantomData<S>,
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for P12<Square12, BB12<Square12>> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error> {
+        PositionData::new::<
+            BB12<Square12>,
+            Attacks12<Square12, BB12<Square12>>,
+            Self,
+        >(self)
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for P12<Square12, BB12<Square12>> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        PositionData::<Square12>::deserialize(deserializer)?
+            .restore::<BB12<Square12>, Attacks12<Square12, BB12<Square12>>, Self>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl<S, B> P12<S, B>
 where
     S: Square,
@@ -60,6 +96,12 @@ impl Board<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
     }
 
     fn set_piece(&mut self, sq: Square12, p: Option<Piece>) {
+        if let Some(old) = self.board.get(sq) {
+            self.zobrist ^= zobrist::piece_key(*old, sq.index());
+        }
+        if let Some(p) = p {
+            self.zobrist ^= zobrist::piece_key(p, sq.index());
+        }
         self.board.set(sq, p)
     }
 
@@ -116,6 +158,10 @@ impl Board<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
         self.ply += 1;
     }
 
+    fn decrement_ply(&mut self) {
+        self.ply = self.ply.saturating_sub(1);
+    }
+
     fn flip_side_to_move(&mut self) {
         self.side_to_move = self.side_to_move.flip();
     }
@@ -140,6 +186,69 @@ impl Board<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
         self.variant = variant;
     }
 
+    fn auto_draw_repetition(&self) -> bool {
+        self.auto_draw_repetition
+    }
+
+    fn auto_draw_material(&self) -> bool {
+        self.auto_draw_material
+    }
+
+    fn update_auto_draw_repetition(&mut self, enabled: bool) {
+        self.auto_draw_repetition = enabled;
+    }
+
+    fn update_auto_draw_material(&mut self, enabled: bool) {
+        self.auto_draw_material = enabled;
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.clock
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    fn record_history(&self) -> bool {
+        self.record_history
+    }
+
+    fn clone_for_search(&self) -> Self {
+        P12 {
+            board: self.board.clone(),
+            hand: self.hand.clone(),
+            ply: self.ply,
+            side_to_move: self.side_to_move,
+            move_history: Vec::new(),
+            occupied_bb: self.occupied_bb,
+            color_bb: self.color_bb,
+            game_status: self.game_status.clone(),
+            variant: self.variant,
+            auto_draw_repetition: self.auto_draw_repetition,
+            auto_draw_material: self.auto_draw_material,
+            halfmove_clock: self.halfmove_clock,
+            type_bb: self.type_bb,
+            record_history: false,
+            zobrist: self.zobrist,
+            clock: self.clock,
+            _a: PhantomData,
+            _s: PhantomData,
+        }
+    }
+
+    fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    fn update_halfmove_clock(&mut self, clock: u16) {
+        self.halfmove_clock = clock;
+    }
+
     fn insert_sfen(&mut self, sfen: Move<Square12>) {
         self.move_history.push(sfen);
     }
@@ -148,6 +257,10 @@ impl Board<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
         self.move_history.push(move_record)
     }
 
+    fn pop_move(&mut self) -> Option<Move<Square12>> {
+        self.move_history.pop()
+    }
+
     fn clear_sfen_history(&mut self) {
         self.move_history.clear();
     }
@@ -193,6 +306,14 @@ impl Board<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
     fn dimensions(&self) -> u8 {
         12
     }
+
+    fn file(&self, f: u8) -> Option<BB12<Square12>> {
+        FILE_BB.get(f as usize).copied()
+    }
+
+    fn rank(&self, r: u8) -> Option<BB12<Square12>> {
+        RANK_BB.get(r as usize).copied()
+    }
 }
 
 impl Sfen<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
@@ -226,11 +347,26 @@ impl Sfen<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
 impl Placement<Square12, BB12<Square12>, Attacks12<Square12, BB12<Square12>>>
     for P12<Square12, BB12<Square12>>
 {
-    fn generate_plinths(&mut self) {
-        let bb = PlinthGen12::default().start();
+    fn generate_plinths_seeded(&mut self, seed: u64) {
+        let mut rng = PlinthRng::new(seed);
+        let bb = PlinthGen12::default().start_with(&mut rng);
         self.color_bb[Color::NoColor.index()] = bb;
     }
 
+    fn plinth_sections(&self) -> Vec<(BB12<Square12>, u32)> {
+        let union = |bbs: &[BB12<Square12>]| {
+            bbs.iter().fold(BB12::empty(), |acc, bb| acc | bb)
+        };
+        let (south, north) = (union(&RANK_BB[..6]), union(&RANK_BB[6..]));
+        let (west, east) = (union(&FILE_BB[..6]), union(&FILE_BB[6..]));
+        vec![
+            (south & &west, 2),
+            (south & &east, 2),
+            (north & &west, 2),
+            (north & &east, 2),
+        ]
+    }
+
     fn white_placement_attacked_ranks(&self) -> BB12<Square12> {
         RANK_BB[1] | &RANK_BB[2]
     }
@@ -356,6 +492,12 @@ impl Default for P12<Square12, BB12<Square12>> {
             type_bb: Default::default(),
             game_status: Outcome::MoveOk,
             variant: Variant::Shuuro,
+            auto_draw_repetition: true,
+            auto_draw_material: true,
+            halfmove_clock: 0,
+            record_history: true,
+            zobrist: 0,
+            clock: None,
             _a: PhantomData,
             _s: PhantomData,
         }
@@ -436,20 +578,29 @@ impl fmt::Display for P12<Square12, BB12<Square12>> {
 #[cfg(test)]
 pub mod position_tests {
 
+    use std::collections::HashMap;
+
     use crate::{
-        attacks::Attacks,
+        attacks::{Attacks, Ray},
         bitboard::BitBoard,
         piece_type::PieceType,
-        position::{Board, MoveType, Outcome, Placement, Play, Sfen},
+        position::{
+            self, Board, GameStage, MoveType, Outcome, Placement, Play,
+            Position, Rules, Sfen,
+        },
         shuuro12::{
             attacks12::Attacks12,
             position12::P12,
             square12::{consts::*, Square12},
         },
         square::Square,
-        Color, Move, Piece, Shop, Variant,
+        Color, Move, MoveError, MoveParseError, Piece, PlacementError,
+        SfenError, Shop, Variant,
     };
 
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
+
     pub const START_POS: &str = "KR55/57/57/57/57/57/57/57/57/57/57/kr55 b - 1";
 
     fn setup() {
@@ -639,6 +790,9 @@ pub mod position_tests {
             if let Some(b) = legal_moves.get(&F8) {
                 assert_eq!(b.len(), i.1);
             }
+            let king_moves = pos.king_moves(Color::Black);
+            assert_eq!(king_moves.len() as u32, i.1);
+            assert!(king_moves.iter().all(|m| m.info().unwrap().0 == F8));
         }
     }
 
@@ -650,6 +804,21 @@ pub mod position_tests {
             .expect("failed to parse sfen string");
         assert_eq!(pos.hand(Piece::from_sfen('p').unwrap()), 12);
         assert_eq!(pos.hand(Piece::from_sfen('P').unwrap()), 9);
+
+        let board = "57/57/57/57/5K6/57/57/57/57/57/57/5k6";
+        let cases = [
+            ("99Q", SfenError::InvalidHand),
+            ("3Q6R2B", SfenError::InvalidHand),
+            ("g", SfenError::InvalidHand),
+            ("Q?", SfenError::IllegalPieceType),
+            ("LQ", SfenError::IllegalPieceType),
+        ];
+        for (hand, error) in cases {
+            let sfen = format!("{board} w {hand} 1");
+            assert_eq!(pos.set_sfen(&sfen), Err(error));
+        }
+        pos.update_variant(Variant::ShuuroFairy);
+        assert!(pos.set_sfen(&format!("{board} w g 1")).is_ok());
     }
 
     #[test]
@@ -783,6 +952,19 @@ pub mod position_tests {
         if let Some(b) = pawn_moves.get(&B11) {
             assert_eq!(b.len(), 2);
         }
+        let captures = position.promotion_captures(Color::White);
+        assert_eq!(captures.len(), 4);
+        assert!(captures.iter().all(|m| m.info() == Some((B11, C12))));
+        assert_eq!(
+            captures.iter().filter_map(|m| m.promotion()).collect_vec(),
+            [
+                PieceType::Queen,
+                PieceType::Rook,
+                PieceType::Bishop,
+                PieceType::Knight
+            ]
+        );
+        assert!(position.promotion_captures(Color::Black).is_empty());
         let result = position.play("b11", "c12");
         assert!(result.is_ok());
         assert_eq!(
@@ -935,36 +1117,35 @@ pub mod position_tests {
     fn make_move() {
         setup();
 
-        let base_sfen =
-            "57/3KRRB5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 w K2RB2P 1";
+        let base_sfen = "57/3KRRB5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 w - 1";
         let test_cases = [
             (
                 D2,
                 E1,
                 false,
                 true,
-                "4K7/4RRB5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 b K2RB2P 2",
+                "4K7/4RRB5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 b - 2 - 1",
             ),
             (
                 E2,
                 E7,
                 false,
                 true,
-                "57/3K1RB5/5PP5/57/57/57/4R7/qbbn8/57/6k5/57/57 b K2RB2P 2",
+                "57/3K1RB5/5PP5/57/57/57/4R7/qbbn8/57/6k5/57/57 b - 2 - 1",
             ),
             (
                 G2,
                 I4,
                 false,
                 true,
-                "57/3KRR6/5PP5/8B3/57/57/57/qbbn8/57/6k5/57/57 b K2RB2P 2",
+                "57/3KRR6/5PP5/8B3/57/57/57/qbbn8/57/6k5/57/57 b - 2 - 1",
             ),
             (
                 F2,
                 F1,
                 false,
                 true,
-                "5R6/3KR1B5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 b K2RB2P 2",
+                "5R6/3KR1B5/5PP5/57/57/57/57/qbbn8/57/6k5/57/57 b - 2 - 1",
             ),
             (G3, H3, false, false, base_sfen),
         ];
@@ -980,15 +1161,26 @@ pub mod position_tests {
 
         let mut pos = P12::new();
         // Leaving the checked king is illegal.
-        pos.set_sfen("57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b kr 1")
+        pos.set_sfen("57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1")
             .expect("failed to parse SFEN string");
         let move_ = Move::new(A6, A1);
         assert!(pos.make_move(move_).is_err());
 
-        pos.set_sfen("7K4/1RR9/57/57/57/r9k1/57/57/57/57/57/57 b kr 1")
+        pos.set_sfen("7K4/1RR9/57/57/57/r9k1/57/57/57/57/57/57 b - 1")
             .expect("failed to parse SFEN string");
         let move_ = Move::new(K6, K5);
         assert!(pos.make_move(move_).is_ok());
+
+        pos.set_sfen("7K4/1RR9/57/57/57/r9k1/57/57/57/57/57/57 b r 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.stage(), GameStage::Deployment);
+        assert_eq!(
+            pos.make_move(Move::new(K6, K5)),
+            Err(MoveError::NotInFight)
+        );
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.stage(), GameStage::Selection);
     }
 
     #[test]
@@ -1180,13 +1372,15 @@ pub mod position_tests {
             (PieceType::Queen, Color::Black, F12),
         ];
         for case in cases {
-            position_set.place(
-                Piece {
-                    piece_type: case.0,
-                    color: case.1,
-                },
-                case.2,
-            );
+            position_set
+                .place(
+                    Piece {
+                        piece_type: case.0,
+                        color: case.1,
+                    },
+                    case.2,
+                )
+                .expect("illegal placement");
         }
         assert!(position_set.is_hand_empty(Color::Black, PieceType::Plinth));
         assert!(position_set.is_hand_empty(Color::White, PieceType::Plinth));
@@ -1205,7 +1399,7 @@ pub mod position_tests {
                 piece_type: PieceType::King,
                 color: Color::White,
             };
-            position_set.place(piece, case.0);
+            assert_eq!(position_set.place(piece, case.0).is_ok(), case.1 == 1);
             assert_eq!(position_set.player_bb(Color::White).len(), case.1);
         }
     }
@@ -1218,6 +1412,18 @@ pub mod position_tests {
         assert_eq!(position_set.player_bb(Color::NoColor).len(), 8);
     }
 
+    #[test]
+    fn generate_plinths_seeded() {
+        setup();
+        let mut first = P12::default();
+        let mut second = P12::default();
+        first.generate_plinths_seeded(42);
+        second.generate_plinths_seeded(42);
+        assert_eq!(first.plinths().len(), 8);
+        assert_eq!(first.plinths(), second.plinths());
+        assert_eq!(first.plinths(), vec![H2, L2, C6, F6, G7, A8, C10, I11]);
+    }
+
     #[test]
     fn flip_empty_side() {
         setup();
@@ -1233,7 +1439,7 @@ pub mod position_tests {
             (Color::Black, PieceType::Pawn, G11),
         ];
         for m in moves {
-            position.place(
+            let _ = position.place(
                 Piece {
                     piece_type: m.1,
                     color: m.0,
@@ -1279,13 +1485,15 @@ pub mod position_tests {
                 .set_sfen(black_fen)
                 .expect("failed to parse sfen string");
 
-            position_set.place(
-                Piece {
-                    piece_type: case,
-                    color: Color::Black,
-                },
-                G11,
-            );
+            position_set
+                .place(
+                    Piece {
+                        piece_type: case,
+                        color: Color::Black,
+                    },
+                    G11,
+                )
+                .expect("illegal placement");
             assert_eq!(position_set.ply(), 4);
         }
     }
@@ -1549,6 +1757,1240 @@ pub mod position_tests {
         }
     }
 
+    #[test]
+    fn phase_ply_counts() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("6K5/57/57/57/57/57/57/57/57/57/57/6k5 w Rr 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.phase_ply_counts(), (0, 0));
+        pos.place(Piece::from_sfen('R').unwrap(), A1)
+            .expect("illegal placement");
+        pos.place(Piece::from_sfen('r').unwrap(), A12)
+            .expect("illegal placement");
+        assert_eq!(pos.phase_ply_counts(), (2, 0));
+        assert!(pos.play("a12", "b12").is_ok());
+        assert!(pos.play("a1", "b1").is_ok());
+        assert!(pos.play("b12", "c12").is_ok());
+        assert_eq!(pos.phase_ply_counts(), (2, 3));
+    }
+
+    #[test]
+    fn captured() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("R5K5/57/56B/57/p56/57/57/57/57/57/57/6k4r w - 1")
+            .expect("failed to parse SFEN string");
+        assert!(pos.play("a1", "a5").is_ok());
+        assert!(pos.play("l12", "l3").is_ok());
+        assert!(pos.play("g1", "f1").is_ok());
+        assert_eq!(pos.captured(Color::White), vec![PieceType::Pawn]);
+        assert_eq!(pos.captured(Color::Black), vec![PieceType::Bishop]);
+    }
+
+    #[test]
+    fn position_key() {
+        setup();
+        let sfen = "RR4K5/57/57/57/57/57/57/57/57/57/57/6k5 w - 1";
+        let mut first = P12::default();
+        first.set_sfen(sfen).expect("failed to parse SFEN string");
+        let mut second = first.clone();
+        for (from, to) in [("a1", "a2"), ("g12", "f12"), ("b1", "b2")] {
+            assert!(first.play(from, to).is_ok());
+        }
+        for (from, to) in [("b1", "b2"), ("g12", "f12"), ("a1", "a2")] {
+            assert!(second.play(from, to).is_ok());
+        }
+        assert_eq!(first.position_key(), second.position_key());
+        assert!(second.play("f12", "e12").is_ok());
+        assert_ne!(first.position_key(), second.position_key());
+
+        let key = |sfen: &str| {
+            let mut pos = P12::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            pos.position_key()
+        };
+        let board = "6K5/57/57/57/57/57/57/57/57/57/57/6k5";
+        assert_ne!(
+            key(&format!("{board} w Q 1")),
+            key(&format!("{board} w R 1"))
+        );
+        assert_ne!(
+            key(&format!("{board} w Q 1")),
+            key(&format!("{board} w QQ 1"))
+        );
+        assert_ne!(
+            key(&format!("{board} w - 1")),
+            key("6K5/L056/57/57/57/57/57/57/57/57/57/6k5 w - 1")
+        );
+    }
+
+    #[test]
+    fn pseudo_legal_moves() {
+        setup();
+        let cases = [
+            "5K6/55PL0/3N8/2L09/5L06/8nL02/1L03Q4L01/7L04/57/57/L03pr6/5k1Q4 b - 50",
+            "57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1",
+            "5NNQK3/8B3/57/57/57/8r3/57/57/pp55/1k55/57/57 w - 1",
+        ];
+        for case in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(case).expect("failed to parse SFEN string");
+            let color = pos.side_to_move();
+            let legal = pos.legal_moves(&color);
+            let pseudo = pos.pseudo_legal_moves(color);
+            assert_eq!(legal.len(), pseudo.len());
+            let mut reduced = false;
+            for (sq, moves) in legal {
+                let pseudo = pseudo.get(&sq).unwrap();
+                assert!((moves & &!*pseudo).is_empty());
+                reduced |= pseudo.len() > moves.len();
+            }
+            assert!(reduced);
+        }
+    }
+
+    #[test]
+    fn place_king_first() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/6k5 w KQ 1")
+            .expect("failed to parse SFEN string");
+        let queen = Piece::from_sfen('Q').unwrap();
+        assert!(pos.must_place_king(Color::White));
+        assert!(!pos.must_place_king(Color::Black));
+        assert_eq!(pos.place(queen, D1), Err(PlacementError::KingNotPlaced));
+        assert!(pos.place(queen, D1).is_err());
+        assert!(pos.place(Piece::from_sfen('K').unwrap(), G1).is_ok());
+        assert!(!pos.must_place_king(Color::White));
+        assert!(pos.place(queen, D1).is_ok());
+        assert_eq!(pos.player_bb(Color::White).len(), 2);
+    }
+
+    #[test]
+    fn placement_targets() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("5KQ2L02/9L02/57/57/3L08/5L06/2L09/2L09/8L03/57/9L02/6k5 b qrn2pN2P 3")
+            .expect("failed to parse SFEN string");
+        let queen = Piece::from_sfen('q').unwrap();
+        let targets = pos.placement_targets(queen);
+        assert!(targets.is_any());
+        assert!(targets.into_iter().all(|sq| sq.file() == 6));
+        assert!(pos.can_place(queen, G11));
+        assert!(!pos.can_place(queen, A12));
+        assert!(pos
+            .placement_targets(Piece::from_sfen('b').unwrap())
+            .is_empty());
+        assert!(pos
+            .placement_targets(Piece::from_sfen('N').unwrap())
+            .is_empty());
+        assert_eq!(pos.ply(), 3);
+    }
+
+    #[test]
+    fn place_in_double_check() {
+        setup();
+        let queen = Piece::from_sfen('Q').unwrap();
+        let mut pos = P12::default();
+        pos.set_sfen("6K5/57/57/57/57/57/57/57/57/57/57/5kr5 w Q 2")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.checks(&Color::White).len(), 2);
+        let mut targets: Vec<Square12> = pos.placement_targets(queen).collect();
+        targets.sort_by_key(|sq| sq.index());
+        assert_eq!(targets, vec![G2, G3]);
+
+        pos.set_sfen("6K5/57/57/57/57/57/b56/57/57/57/57/5kr5 w Q 2")
+            .expect("failed to parse SFEN string");
+        assert!(pos.checks(&Color::White).is_empty());
+        assert!(pos.placement_targets(queen).is_empty());
+    }
+
+    #[test]
+    fn king_drop_squares() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/5r6 w K 1")
+            .expect("failed to parse SFEN string");
+        let squares = pos.king_drop_squares(Color::White);
+        assert_eq!(squares.len(), 5);
+        assert!((squares & &F1).is_empty());
+        assert!((squares & &D1).is_any());
+        assert_eq!(pos.king_squares::<6>(&Color::White).len(), 6);
+    }
+
+    #[test]
+    fn children() {
+        setup();
+        let cases = [
+            "57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1",
+            "5NNQK3/8B3/57/57/57/8r3/57/57/pp55/1k55/57/57 w - 1",
+        ];
+        for case in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(case).expect("failed to parse SFEN string");
+            let color = pos.side_to_move();
+            let children = pos.children(color);
+            assert_eq!(children.len(), pos.legal_move_count(color));
+            assert_eq!(pos.all_moves(color).len(), children.len());
+            for (m, child) in children {
+                assert_eq!(child.side_to_move(), color.flip());
+                let last = child.move_history().last().unwrap();
+                assert_eq!(last.info(), m.info());
+            }
+        }
+    }
+
+    #[test]
+    fn only_kings() {
+        setup();
+        let cases = [
+            ("57/1K55/57/L056/57/57/57/57/7L04/57/57/9k2 b - 1", true),
+            ("57/1K55/57/L056/57/57/57/57/7L04/57/57/8nk2 b - 1", false),
+            ("57/1K55/57/57/5P6/57/57/57/57/57/57/9k2 b - 1", false),
+        ];
+        for (sfen, expected) in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(pos.only_kings(), expected);
+        }
+    }
+
+    #[test]
+    fn ray_attack() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("R8K2/57/57/L056/57/57/57/57/57/57/57/9k2 w - 1")
+            .expect("failed to parse SFEN string");
+        let north = pos.ray_attack(A1, Ray::North);
+        assert!((north & &A4).is_any());
+        assert!((north & &A5).is_empty());
+        assert_eq!(north.len(), 3);
+        let east = pos.ray_attack(A1, Ray::East);
+        assert_eq!(east.len(), 9);
+        assert!(pos.ray_attack(A1, Ray::South).is_empty());
+    }
+
+    #[test]
+    fn move_counts() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("5NNQK3/8B3/57/57/57/8r3/57/57/pp55/1k55/57/57 w - 1")
+            .expect("failed to parse SFEN string");
+        let counts = pos.move_counts(Color::White);
+        assert_eq!(counts.len(), 5);
+        let sum: u32 = counts.values().sum();
+        assert_eq!(sum as usize, pos.legal_move_count(Color::White));
+    }
+
+    #[test]
+    fn is_mirror_deployment() {
+        setup();
+        let cases = [
+            ("3Q2K5/1N55/57/57/57/57/57/57/57/57/1n55/3q2k5 w - 1", true),
+            ("3Q2K5/1N55/57/57/57/57/57/57/57/57/1n55/4q1k5 w - 1", false),
+            ("3Q2K5/1N55/57/57/57/57/57/57/57/57/1b55/3q2k5 w - 1", false),
+            ("3Q2K5/57/57/57/57/57/57/57/57/57/1n55/3q2k5 w - 1", false),
+        ];
+        for (sfen, expected) in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(pos.is_mirror_deployment(), expected);
+        }
+    }
+
+    #[test]
+    fn legal_moves_without() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        let pinned = pos.legal_moves(&Color::White);
+        let pinned = pinned.get(&A5).unwrap();
+        assert!((*pinned & &B5).is_empty());
+        let free = pos.legal_moves_without(Color::White, A12);
+        let free = free.get(&A5).unwrap();
+        assert!((*free & &B5).is_any());
+        assert!(pos.piece_at(A12).is_some());
+    }
+
+    #[test]
+    fn hand_sfen() {
+        setup();
+        let cases = [
+            "57/57/57/57/57/57/57/57/57/57/57/6k5 w KQrn 1",
+            "K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1",
+        ];
+        for case in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(case).expect("failed to parse SFEN string");
+            let sfen = pos.generate_sfen();
+            let field = sfen.split(' ').nth(2).unwrap();
+            assert_eq!(pos.hand_sfen(), field);
+        }
+    }
+
+    #[test]
+    fn auto_draw_repetition() {
+        setup();
+        let moves = [
+            ("a1", "a2"),
+            ("l12", "l11"),
+            ("a2", "a1"),
+            ("l11", "l12"),
+            ("a1", "a2"),
+            ("l12", "l11"),
+            ("a2", "a1"),
+            ("l11", "l12"),
+        ];
+        for enabled in [true, false] {
+            let mut pos = P12::default();
+            pos.set_sfen("K1Q9/57/57/57/57/57/57/57/57/57/57/55rk w - 1")
+                .expect("failed to parse SFEN string");
+            pos.update_auto_draw_repetition(enabled);
+            for (from, to) in moves {
+                pos.play(from, to).expect("illegal move");
+            }
+            let last = pos.make_move(Move::new(A1, A2));
+            if enabled {
+                assert_eq!(last, Err(MoveError::RepetitionDraw));
+            } else {
+                assert_eq!(last, Ok(Outcome::MoveOk));
+            }
+        }
+    }
+
+    #[test]
+    fn repetition_in_long_game() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K1Q9/57/57/57/57/57/57/57/57/57/57/55rk w - 1")
+            .expect("failed to parse SFEN string");
+        let files = ["c", "d", "e", "f", "g", "h", "i"];
+        for i in 0..6 {
+            pos.play(&format!("{}1", files[i]), &format!("{}1", files[i + 1]))
+                .expect("illegal move");
+            pos.play(&format!("k{}", 12 - i), &format!("k{}", 11 - i))
+                .expect("illegal move");
+        }
+        for _ in 0..2 {
+            for (from, to) in
+                [("a1", "a2"), ("l12", "l11"), ("a2", "a1"), ("l11", "l12")]
+            {
+                pos.play(from, to).expect("illegal move");
+            }
+        }
+        assert_eq!(
+            pos.make_move(Move::new(A1, A2)),
+            Err(MoveError::RepetitionDraw)
+        );
+    }
+
+    #[test]
+    fn space_control() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("KN1Q3B4/57/57/57/57/57/57/57/57/57/57/55nk w - 1")
+            .expect("failed to parse SFEN string");
+        assert!(pos.space_control() > 0);
+        pos.set_sfen("K56/57/57/57/57/57/57/57/57/57/57/55kq w - 1")
+            .expect("failed to parse SFEN string");
+        assert!(pos.space_control() < 0);
+    }
+
+    #[test]
+    fn place_without_self_check() {
+        setup();
+        let queen = Piece::from_sfen('Q').unwrap();
+        let mut pos = P12::default();
+        pos.set_sfen("6K5/57/57/57/57/57/57/57/57/57/57/k5r5 w Q 1")
+            .expect("failed to parse SFEN string");
+        assert!((pos.empty_squares(queen) & &G2).is_any());
+        pos.set_sfen("57/6K5/57/57/57/57/57/57/57/57/57/k5r5 w Q 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.empty_squares(queen).squares(), vec![G3]);
+        assert_eq!(pos.place(queen, F2), Err(PlacementError::IllegalSquare));
+        assert!(pos.place(queen, G3).is_ok());
+    }
+
+    #[test]
+    fn is_capture() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        assert!(pos.is_capture(&Move::new(A5, A12)));
+        assert!(!pos.is_capture(&Move::new(A5, A6)));
+        assert!(!pos.is_capture(&Move::new(A5, A1)));
+        assert!(!pos.is_capture(&Move::new(B5, A12)));
+    }
+
+    #[test]
+    fn random_game() {
+        setup();
+        let sfen = "5NNQK3/8B3/57/57/57/8r3/57/57/pp55/1k55/57/57 w - 1";
+        let mut pos = P12::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        let moves = pos.random_game(7, 40);
+        assert!(!moves.is_empty() && moves.len() <= 40);
+        let mut replay = P12::default();
+        replay.set_sfen(sfen).expect("failed to parse SFEN string");
+        for m in moves.iter() {
+            let (from, to) = m.info().unwrap();
+            replay
+                .play(&from.to_string(), &to.to_string())
+                .expect("illegal move");
+        }
+        assert_eq!(replay.generate_sfen(), pos.generate_sfen());
+        let mut again = P12::default();
+        again.set_sfen(sfen).expect("failed to parse SFEN string");
+        assert_eq!(again.random_game(7, 40), moves);
+    }
+
+    #[test]
+    fn mobility_delta() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("R5K5/N56/57/57/57/57/57/57/57/57/57/56k w - 1")
+            .expect("failed to parse SFEN string");
+        let delta = pos.mobility_delta(&Move::new(A2, C3));
+        assert_eq!(delta, Ok(16));
+        assert!(pos.mobility_delta(&Move::new(A1, A3)).is_err());
+    }
+
+    #[test]
+    fn clear_line() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K56/57/57/L056/57/57/57/57/57/57/57/56k w - 1")
+            .expect("failed to parse SFEN string");
+        assert!(pos.clear_line(A1, F6));
+        assert!(pos.clear_line(A1, A4));
+        assert!(!pos.clear_line(A1, A6));
+        assert!(!pos.clear_line(A1, B3));
+    }
+
+    #[test]
+    fn reachable() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("R5K5/N56/57/57/57/57/57/57/57/57/57/56k w - 1")
+            .expect("failed to parse SFEN string");
+        let reachable = pos.reachable(Color::White);
+        assert_eq!(reachable.len(), 11);
+        for sq in [B1, F1, B4, C3, C1, F2, G2, H2, H1] {
+            assert!((reachable & &sq).is_any());
+        }
+        assert!((reachable & &A3).is_empty());
+    }
+
+    #[test]
+    fn king_defenders() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("5RK5/5PPP4/4N7/57/57/57/57/57/57/57/57/Q55k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.king_defenders(Color::White), 2);
+        assert_eq!(pos.king_defenders(Color::Black), 0);
+    }
+
+    #[test]
+    fn normalize_sfen() {
+        setup();
+        let expected = "KR55/57/57/57/57/57/57/57/57/57/57/kr55 b qN 1";
+        let cases = [
+            expected,
+            "KR91/57/57/57/57/57/57/57/57/57/57/kr4411 b qN 1",
+            "  KR55/57/57/57/57/57/57/57/57/57/57/kr55   b  Nq 1 ",
+        ];
+        for case in cases {
+            assert_eq!(P12::normalize_sfen(case), Ok(String::from(expected)));
+        }
+        assert!(P12::normalize_sfen("KR55/57/57 b - 1").is_err());
+        assert!(P12::normalize_sfen("KR55/57/57/57/57/57/57/57").is_err());
+    }
+
+    #[test]
+    fn perft() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K56/57/57/57/57/57/57/57/57/57/57/56k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.perft(0), 1);
+        assert_eq!(pos.perft(1), 3);
+        assert_eq!(pos.perft(2), 9);
+        let cases = [
+            "57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1",
+            "4KN1Q4/4L0P1P1PP1/8r3/3L08/56L0/6L05/4L01L01q3/57/57/6L05/pP3k2ppp1/L01r9 w - 33",
+        ];
+        for case in cases {
+            pos.set_sfen(case).expect("failed to parse SFEN string");
+            let color = pos.side_to_move();
+            let moves = pos.all_moves(color).len();
+            assert_eq!(pos.perft(1), moves as u64);
+            let divide = pos.perft_divide(2);
+            assert_eq!(divide.len(), moves);
+            assert_eq!(divide.values().sum::<u64>(), pos.perft(2));
+        }
+    }
+
+    #[test]
+    fn drop_attacks() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("6K5/57/57/L056/57/57/57/57/57/57/57/6k5 w R 1")
+            .expect("failed to parse SFEN string");
+        let rook = Piece::from_sfen('R').unwrap();
+        let attacks = pos.drop_attacks(rook, A2);
+        assert!((attacks & &A3).is_any());
+        assert!((attacks & &A4).is_any());
+        assert!((attacks & &A5).is_empty());
+        assert!((attacks & &L2).is_any());
+        assert!((attacks & &A1).is_any());
+        assert_eq!(attacks.len(), 14);
+    }
+
+    #[test]
+    fn all_moves_worsen() {
+        setup();
+        let cases = [
+            ("K9R1/57/1P55/57/2p9/57/57/57/57/57/R56/56k b - 1", true),
+            ("K9R1/57/57/57/2p9/57/57/57/57/57/R56/56k b - 1", false),
+        ];
+        for (sfen, expected) in cases {
+            let mut pos = P12::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(pos.king_escape_count(Color::Black), 0);
+            assert_eq!(pos.all_moves_worsen(), expected);
+        }
+    }
+
+    #[test]
+    fn unmake_move() {
+        setup();
+        let sfen =
+            "4KN1Q4/4L0P1P1PP1/8r3/3L08/56L0/6L05/4L01L01q3/57/57/6L05/pP3k2ppp1/L01r9 w - 33";
+        let mut pos = P12::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        assert!(pos.unmake_move().is_err());
+        let start = pos.generate_sfen();
+        let moves = [("b11", "c12"), ("i3", "i2"), ("h1", "i2"), ("i7", "i2")];
+        let mut positions = vec![start.clone()];
+        for (from, to) in moves {
+            pos.play(from, to).expect("illegal move");
+            positions.push(pos.generate_sfen());
+        }
+        assert_eq!(pos.piece_at(C12).unwrap().piece_type, PieceType::Queen);
+        for expected in positions.iter().rev().skip(1) {
+            assert!(pos.unmake_move().is_ok());
+            assert_eq!(&pos.generate_sfen(), expected);
+        }
+        assert_eq!(pos.piece_at(B11).unwrap().piece_type, PieceType::Pawn);
+        assert!(pos.piece_at(C12).unwrap().color == Color::Black);
+        assert!(pos.move_history().is_empty());
+        let mut fresh = P12::default();
+        fresh.set_sfen(sfen).expect("failed to parse SFEN string");
+        assert_eq!(pos.position_key(), fresh.position_key());
+        assert!((pos.occupied_bb() ^ &fresh.occupied_bb()).is_empty());
+    }
+
+    #[test]
+    fn training_records() {
+        setup();
+        let sfen = "5NNQK3/8B3/57/57/57/8r3/57/57/pp55/1k55/57/57 w - 1";
+        let mut pos = P12::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        let moves = pos.random_game(3, 12);
+        let records = pos.training_records().expect("failed to unmake move");
+        assert_eq!(records.len(), moves.len() + 1);
+        assert_eq!(records[0].0, P12::normalize_sfen(sfen).unwrap());
+        assert_eq!(records.last().unwrap().0, pos.generate_sfen());
+        assert!(records.last().unwrap().1.is_none());
+        for (record, m) in records.iter().zip(moves.iter()) {
+            assert_eq!(record.1.as_ref().unwrap().info(), m.info());
+            assert_eq!(&record.2, pos.outcome());
+        }
+
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/6k5 w KQ 1")
+            .expect("failed to parse SFEN string");
+        pos.place(Piece::from_sfen('K').unwrap(), G1).unwrap();
+        assert!(pos.training_records().is_err());
+    }
+
+    #[test]
+    fn all_placements() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/6k5 w KQ 1")
+            .expect("failed to parse SFEN string");
+        let placements = pos.all_placements(Color::White);
+        assert_eq!(placements.len(), 6);
+        assert!(pos.all_placements(Color::Black).is_empty());
+        pos.place(Piece::from_sfen('K').unwrap(), G1).unwrap();
+        let queen = Piece::from_sfen('Q').unwrap();
+        let placements = pos.all_placements(Color::White);
+        assert_eq!(placements.len() as u32, pos.empty_squares(queen).len());
+        for m in placements {
+            if let Move::Put { to, piece, .. } = m {
+                assert_eq!(piece, queen);
+                assert!(pos.clone().place(piece, to).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn non_hanging_moves() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K2Q8/57/57/57/57/2r9/57/57/57/57/57/56k w - 1")
+            .expect("failed to parse SFEN string");
+        let legal = pos.legal_moves(&Color::White);
+        let safe = pos.non_hanging_moves(Color::White);
+        let queen = *legal.get(&D1).unwrap();
+        let safe_queen = *safe.get(&D1).unwrap();
+        assert!((queen & &D6).is_any());
+        assert!((safe_queen & &D6).is_empty());
+        assert!((safe_queen & &D5).is_any());
+        assert!((safe_queen & &D2).is_any());
+
+        pos.set_sfen("K2Q8/57/57/57/2P9/3p8/4p7/57/57/57/57/11k w - 1")
+            .expect("failed to parse SFEN string");
+        let safe = pos.non_hanging_moves(Color::White);
+        assert!(pos.see(D6, Color::White) >= 0);
+        assert!(pos.see_move(&Move::new(D1, D6)) < 0);
+        assert!((*safe.get(&D1).unwrap() & &D6).is_empty());
+        assert!((*safe.get(&C5).unwrap() & &D6).is_any());
+    }
+
+    #[test]
+    fn fifty_moves() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("K56/P56/57/57/R56/57/57/57/57/57/57/r55k w - 1 - 99")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.halfmove_clock(), 99);
+        pos.play("a2", "a3").expect("illegal move");
+        assert_eq!(pos.halfmove_clock(), 0);
+        assert_eq!(pos.outcome(), &Outcome::MoveOk);
+
+        pos.set_sfen("K56/P56/57/57/R56/57/57/57/57/57/57/r55k w - 1 - 99")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.play("a5", "b5").expect("illegal move"),
+            &Outcome::DrawByFiftyMoves
+        );
+        assert_eq!(pos.halfmove_clock(), 100);
+        assert!(pos.generate_sfen().ends_with(" b - 2 - 100"));
+
+        pos.set_sfen("K56/57/57/57/57/57/57/57/57/1R55/R56/56k w - 1 - 99")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.play("b10", "b12").expect("illegal move"),
+            &Outcome::Checkmate {
+                color: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn halfmove_clock_sfen() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("K56/P56/57/57/R56/57/57/57/57/57/57/r55k w - 1 - 42")
+            .expect("failed to parse SFEN string");
+        pos.play("a5", "b5").expect("illegal move");
+        let sfen = pos.generate_sfen();
+        assert!(sfen.ends_with(" b - 2 - 43"));
+        let mut parsed = P12::new();
+        parsed.set_sfen(&sfen).expect("failed to parse SFEN string");
+        assert_eq!(parsed.variant(), Variant::Shuuro);
+        assert_eq!(parsed.halfmove_clock(), 43);
+        assert_eq!(parsed.generate_sfen(), sfen);
+
+        pos.play("l12", "l11").expect("illegal move");
+        pos.play("a2", "a3").expect("illegal move");
+        assert!(pos.generate_sfen().ends_with(" b - 4"));
+    }
+
+    #[test]
+    fn material_balance() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("R5K5/57/56B/57/p56/57/57/57/57/57/57/6k4r w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.material_balance(&HashMap::new()), 30);
+        let values = HashMap::from([(PieceType::Bishop, 30)]);
+        assert_eq!(pos.material_balance(&values), 20);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        setup();
+        let mut pos = P12::default();
+        pos.update_variant(Variant::ShuuroFairy);
+        pos.set_sfen("6K5/57/57/57/57/57/57/57/57/57/57/6k5 w RNr 1")
+            .expect("failed to parse SFEN string");
+        pos.place(Piece::from_sfen('R').unwrap(), A1)
+            .expect("illegal placement");
+        let json = serde_json::to_string(&pos).expect("failed to serialize");
+        let restored: P12<Square12, _> =
+            serde_json::from_str(&json).expect("failed to deserialize");
+        assert_eq!(restored.generate_sfen(), pos.generate_sfen());
+        assert_eq!(restored.move_history(), pos.move_history());
+        assert_eq!(restored.variant(), Variant::ShuuroFairy);
+        let rook = Piece::from_sfen('r').unwrap();
+        let squares = pos.empty_squares(rook);
+        assert_eq!(
+            (restored.empty_squares(rook) & &squares).len(),
+            squares.len()
+        );
+
+        pos.set_sfen("K56/P56/57/57/R56/57/57/57/57/57/57/r55k w - 1 - 42")
+            .expect("failed to parse SFEN string");
+        let json = serde_json::to_string(&pos).expect("failed to serialize");
+        let restored: P12<Square12, _> =
+            serde_json::from_str(&json).expect("failed to deserialize");
+        assert_eq!(restored.halfmove_clock(), 42);
+        assert_eq!(restored.clock(), None);
+
+        let clock = position::Clock {
+            white_ms: 60_000,
+            black_ms: 45_000,
+            increment_ms: 2_000,
+        };
+        pos.set_clock(clock);
+        let json = serde_json::to_string(&pos).expect("failed to serialize");
+        let restored: P12<Square12, _> =
+            serde_json::from_str(&json).expect("failed to deserialize");
+        assert_eq!(restored.clock(), Some(clock));
+    }
+
+    #[test]
+    fn board_state() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("6K5/3L08/57/57/57/57/57/57/57/57/L056/6k5 w Rrr 1")
+            .expect("failed to parse SFEN string");
+        let white_king = Piece::from_sfen('K').unwrap();
+        let black_king = Piece::from_sfen('k').unwrap();
+        assert_eq!(
+            pos.board_state(),
+            vec![(G1, white_king), (G12, black_king)]
+        );
+        assert_eq!(pos.plinths(), vec![D2, A11]);
+        assert_eq!(
+            pos.hands(),
+            [
+                (Color::White, vec![(PieceType::Rook, 1)]),
+                (Color::Black, vec![(PieceType::Rook, 2)])
+            ]
+        );
+    }
+
+    #[test]
+    fn shop_affordable() {
+        let mut shop = Shop::<Square12>::default();
+        shop.update_variant(Variant::ShuuroMini);
+        shop.set_hand("KQk");
+        let affordable = shop.affordable(Color::White);
+        assert!(affordable.contains(&(PieceType::Queen, 0)));
+        assert!(affordable.contains(&(PieceType::Rook, 1)));
+        assert!(affordable.contains(&(PieceType::Pawn, 9)));
+        assert!(affordable.contains(&(PieceType::Giraffe, 1)));
+        assert!(shop
+            .affordable(Color::Black)
+            .contains(&(PieceType::Queen, 1)));
+
+        shop.update_variant(Variant::Shuuro);
+        assert!(!shop
+            .affordable(Color::White)
+            .iter()
+            .any(|(pt, _)| pt.is_fairy_piece()));
+    }
+
+    #[test]
+    fn set_plinths() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("6K5/57/57/L056/57/57/57/57/57/57/57/6k5 w N 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.set_plinths(&[A1, B1, C1, D1, E1, F1, H1, I1, J1]),
+            Err(PlacementError::TooManyPlinths)
+        );
+        assert_eq!(pos.set_plinths(&[G1]), Err(PlacementError::IllegalSquare));
+        assert_eq!(
+            pos.set_plinths(&[A1, C3, F6]),
+            Err(PlacementError::TooManyPlinths)
+        );
+        assert_eq!(pos.plinths(), vec![A4]);
+        assert!(pos.set_plinths(&[C1, J11]).is_ok());
+        assert_eq!(pos.plinths(), vec![C1, J11]);
+        let plinths = pos.player_bb(Color::NoColor);
+        assert_eq!(plinths.squares().len() as u32, plinths.len());
+        assert_eq!(pos.type_bb(&PieceType::Plinth), plinths);
+        assert!((pos.occupied_bb() & &J11).is_any());
+        assert!(pos.piece_at(J11).is_none());
+        assert!(pos.place(Piece::from_sfen('N').unwrap(), C1).is_ok());
+        pos.clear_plinths();
+        assert!(pos.plinths().is_empty());
+        assert!(pos.piece_at(C1).is_some());
+    }
+
+    #[test]
+    fn illegal_move_square() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.make_move(Move::new(A5, B6)),
+            Err(MoveError::IllegalMove {
+                from: String::from("a5"),
+                to: String::from("b6"),
+                reason: "The piece cannot move to there",
+            })
+        );
+        assert!(matches!(
+            pos.make_move(Move::new(A12, A11)),
+            Err(MoveError::IllegalMove { .. })
+        ));
+    }
+
+    #[test]
+    fn to_pgn_placement() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("6K5/57/57/57/57/57/57/57/57/57/57/6k5 w Rr 1")
+            .expect("failed to parse SFEN string");
+        pos.place(Piece::from_sfen('R').unwrap(), A1)
+            .expect("illegal placement");
+        pos.place(Piece::from_sfen('r').unwrap(), A12)
+            .expect("illegal placement");
+        pos.play("a12", "b12").expect("illegal move");
+        let pgn = pos.to_pgn();
+        assert!(pgn.contains("{ R@a1 r@a12 } 1... Rb12 *"));
+    }
+
+    #[test]
+    fn legal_moves_from() {
+        setup();
+        let sfens = [
+            "57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b kr 1",
+            "R5K5/57/56B/57/p56/57/57/57/57/57/57/6k4r w - 1",
+            "57/57/PPPQP4K2/7RR3/57/57/57/4pp6/2kr8/57/57/57 b - 1",
+            "7K4/1L01p1N6/57/5R2B3/1L05L04/9L02/57/5L06/57/7L04/5L04L01/2r2k1n4 b - 28",
+        ];
+        for sfen in sfens {
+            let mut pos = P12::new();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            let color = pos.side_to_move();
+            let legal_moves = pos.legal_moves(&color);
+            for sq in Square12::iter() {
+                let moves = pos.legal_moves_from(&sq);
+                match legal_moves.get(&sq) {
+                    Some(expected) => {
+                        assert_eq!(moves.len(), expected.len());
+                        assert_eq!((moves & expected).len(), expected.len());
+                    }
+                    None => assert!(moves.is_empty()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resign_and_draw() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        pos.resign(Color::White);
+        assert_eq!(
+            pos.outcome(),
+            &Outcome::Resign {
+                color: Color::White
+            }
+        );
+        assert_eq!(pos.make_move(Move::new(A5, B5)), Err(MoveError::GameEnded));
+        assert!(pos.to_pgn().ends_with("0-1"));
+        pos.agree_draw();
+        assert!(pos.to_pgn().ends_with("1/2-1/2"));
+        pos.flag(Color::Black);
+        assert_eq!(pos.outcome().to_string(), "LostOnTime_b");
+        assert!(pos.play("a5", "b5").is_err());
+    }
+
+    #[test]
+    fn checkers() {
+        setup();
+        let cases = [
+            ("57/57/57/57/5K6/57/57/57/57/57/5r6/5k6 w - 1", vec![F11]),
+            (
+                "57/57/57/57/5K6/57/6n5/57/57/57/5r6/5k6 w - 1",
+                vec![G7, F11],
+            ),
+            ("57/57/57/57/5K6/57/57/57/57/57/57/5k6 w - 1", vec![]),
+        ];
+        for case in cases {
+            let mut pos = P12::new();
+            pos.set_sfen(case.0).expect("failed to parse SFEN string");
+            let mut checkers: Vec<Square12> =
+                pos.checkers(Color::White).collect();
+            checkers.sort_by_key(|sq| sq.index());
+            assert_eq!(checkers, case.1);
+        }
+    }
+
+    #[test]
+    fn set_sfen_illegal_board() {
+        setup();
+        let cases = [
+            ("57/57/57/57/5K6/57/57/57/57/57/5K6/5k6 w - 1", false),
+            ("57/57/57/57/5Q6/57/57/57/57/57/57/5k6 w - 1", false),
+            ("57/57/57/57/5K6/57/57/57/57/57/57/5kP5 w - 1", false),
+            ("57/57/57/57/5K6/57/57/57/57/57/57/5k6 w RRRRRRR 1", false),
+            ("57/57/57/57/5K6/57/57/57/57/57/57/5k6 w QQQQ 1", false),
+            ("57/57/57/57/5K6/57/57/57/57/57/QQQQ53/5k6 w - 1", true),
+            ("57/57/57/57/5K6/57/57/57/57/57/57/5k6 w Rr 1", true),
+            ("57/57/57/57/57/57/57/57/57/57/57/57 w Kk 1", true),
+        ];
+        for (sfen, ok) in cases {
+            let mut pos = P12::new();
+            assert_eq!(pos.set_sfen(sfen).is_ok(), ok, "{sfen}");
+        }
+        let mut pos = P12::new();
+        assert_eq!(
+            pos.set_sfen("57/57/57/57/5K6/57/57/57/57/57/5K6/5k6 w - 1"),
+            Err(SfenError::IllegalBoardState)
+        );
+    }
+
+    #[test]
+    fn under_promotion_sfen() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("KNNNNNNNNN2/57/57/57/57/57/57/57/57/57/P56/56k w - 1")
+            .expect("failed to parse SFEN string");
+        pos.make_move(Move::new_promotion(A11, A12, PieceType::Knight))
+            .expect("illegal move");
+        let sfen = pos.generate_sfen();
+        let mut parsed = P12::new();
+        parsed.set_sfen(&sfen).expect("failed to parse SFEN string");
+        assert_eq!(parsed.generate_sfen(), sfen);
+        assert_eq!(parsed.piece_at(A12).unwrap().piece_type, PieceType::Knight);
+    }
+
+    #[test]
+    fn try_move() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1")
+            .expect("failed to parse SFEN string");
+        pos.play("k6", "j7").expect("illegal move");
+        let child = pos.try_move(Move::new(B2, B3)).expect("illegal move");
+        let probed = pos.probe(Move::new(B2, B3)).expect("illegal move");
+        assert!(child.move_history().is_empty());
+        assert_eq!(probed.move_history().len(), 2);
+        assert_eq!(
+            child.generate_sfen().split(' ').next(),
+            probed.generate_sfen().split(' ').next()
+        );
+        assert_eq!(child.side_to_move(), Color::Black);
+        assert_eq!(pos.move_history().len(), 1);
+        assert!(pos.try_move(Move::new(B2, B4)).is_err());
+    }
+
+    #[test]
+    fn attacks_from() {
+        setup();
+        let mut pos = P12::new();
+        pos.set_sfen("57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1")
+            .expect("failed to parse SFEN string");
+        let rook = pos.attacks_from(&K2);
+        assert!(rook.into_iter().any(|sq| sq == K6));
+        assert!(!rook.into_iter().any(|sq| sq == L2 || sq == K7));
+        assert_eq!(rook.len(), 13);
+        assert_eq!(pos.attacks_from(&A6).len(), 20);
+        assert!(pos.attacks_from(&C3).is_empty());
+    }
+
+    #[test]
+    fn move_try_from_str() {
+        let ok: [(&str, Move<Square12>); 4] = [
+            ("a1_a12", Move::new(A1, A12)),
+            ("a11_a12n", Move::new_promotion(A11, A12, PieceType::Knight)),
+            (
+                "Q@c3",
+                Move::Put {
+                    piece: Piece::from_sfen('Q').unwrap(),
+                    to: C3,
+                    fen: String::new(),
+                },
+            ),
+            (
+                "+n",
+                Move::Buy {
+                    piece: Piece::from_sfen('n').unwrap(),
+                },
+            ),
+        ];
+        for (s, m) in ok {
+            assert_eq!(Move::try_from(s), Ok(m));
+        }
+        let errors = [
+            ("g8-f6", MoveParseError::MissingSeparator),
+            ("g8_m6", MoveParseError::BadSquare(String::from("m6"))),
+            ("a11_a12k", MoveParseError::BadPromotion(String::from("k"))),
+            ("a11_a12x", MoveParseError::BadPromotion(String::from("x"))),
+            ("X@a1", MoveParseError::UnknownPiece(String::from("X"))),
+            ("L@a1", MoveParseError::UnknownPiece(String::from("L"))),
+            ("Q@a13", MoveParseError::BadSquare(String::from("a13"))),
+        ];
+        for (s, error) in errors {
+            assert_eq!(Move::<Square12>::try_from(s), Err(error));
+        }
+    }
+
+    #[test]
+    fn parse_sfen_fields() {
+        let fields = position::parse_sfen_fields(
+            "57/57/57/57/57/57/57/57/57/57/57/6k5 b K2Qr 17 Kq 4",
+        )
+        .expect("failed to parse SFEN string");
+        assert_eq!(fields.board, "57/57/57/57/57/57/57/57/57/57/57/6k5");
+        assert_eq!(fields.side_to_move, Color::Black);
+        assert_eq!(fields.hand.get(Piece::from_sfen('Q').unwrap()), 2);
+        assert_eq!(fields.hand.get(Piece::from_sfen('r').unwrap()), 1);
+        assert_eq!(fields.ply, 17);
+        assert_eq!(fields.castling_rights, [true, false, false, true]);
+        assert_eq!(fields.halfmove_clock, 4);
+
+        let fields = position::parse_sfen_fields("57 w - 3")
+            .expect("failed to parse SFEN string");
+        assert_eq!(fields.castling_rights, [false; 4]);
+        assert_eq!(fields.halfmove_clock, 0);
+        for (sfen, error) in [
+            ("57 w -", SfenError::MissingDataFields),
+            ("57 x - 3", SfenError::IllegalSideToMove),
+            ("57 w - 3 X", SfenError::IllegalCastlingRights),
+        ] {
+            assert_eq!(position::parse_sfen_fields(sfen).err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn has_any_legal_move() {
+        setup();
+        let sfens = [
+            "57/1K8RR/57/57/57/r9k1/57/57/57/57/57/57 b - 1",
+            "57/57/PPPQP4K2/7RR3/57/57/57/4pp6/2kr8/57/57/57 b - 1",
+            "K56/2q9/1k55/57/57/57/57/57/57/57/57/57 w - 1",
+        ];
+        for sfen in sfens {
+            let mut pos = P12::new();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            for color in [Color::White, Color::Black] {
+                let any = pos.legal_moves(&color).values().any(|m| m.is_any());
+                assert_eq!(pos.has_any_legal_move(color), any, "{sfen}");
+            }
+        }
+    }
+
+    #[test]
+    fn outcome_winner() {
+        let cases = [
+            (
+                Outcome::Checkmate {
+                    color: Color::White,
+                },
+                true,
+                Some(Color::White),
+            ),
+            (
+                Outcome::Resign {
+                    color: Color::White,
+                },
+                true,
+                Some(Color::Black),
+            ),
+            (
+                Outcome::LostOnTime {
+                    color: Color::Black,
+                },
+                true,
+                Some(Color::White),
+            ),
+            (Outcome::Stalemate, true, None),
+            (Outcome::DrawByRepetition, true, None),
+            (Outcome::DrawByAgreement, true, None),
+            (
+                Outcome::Check {
+                    color: Color::White,
+                },
+                false,
+                None,
+            ),
+            (Outcome::MoveOk, false, None),
+            (Outcome::Nothing, false, None),
+        ];
+        for (outcome, terminal, winner) in cases {
+            assert_eq!(outcome.is_terminal(), terminal, "{outcome}");
+            assert_eq!(outcome.winner(), winner, "{outcome}");
+        }
+    }
+
+    #[test]
+    fn random_move() {
+        setup();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut pos = P12::default();
+        pos.set_sfen("5KQ2L02/9L02/57/57/3L08/5L06/2L09/2L09/8L03/57/9L02/6k5 b qrn2pN2P 3")
+            .expect("failed to parse SFEN string");
+        for _ in 0..200 {
+            match pos.random_move(&mut rng) {
+                Some(Move::Put { piece, to, .. }) => {
+                    assert!(pos.place(piece, to).is_ok());
+                }
+                Some(m) => {
+                    let result = pos.make_move(m);
+                    assert!(pos.settle_move(result).is_ok());
+                }
+                None => break,
+            }
+        }
+        assert_eq!(pos.stage(), GameStage::Fight);
+
+        let mut pos = P12::default();
+        pos.set_sfen("K55k/57/57/57/57/57/57/57/57/57/P56/57 w - 1")
+            .expect("failed to parse SFEN string");
+        let promotions: Vec<PieceType> = (0..100)
+            .filter_map(|_| pos.random_move(&mut rng)?.promotion())
+            .unique()
+            .collect();
+        assert_eq!(promotions.len(), 4);
+        pos.resign(Color::White);
+        assert!(pos.random_move(&mut rng).is_none());
+    }
+
+    #[test]
+    fn parse_plinth_notation() {
+        setup();
+        let legacy = "6L03B1/2LN2K3P2/3pPL04L01/57/57/57/7L04/3L08/8L03/q56/L056/3kqbr5 b - 38";
+        let short =
+            "6.3B1/2_N2K3P2/3pP.4.1/57/57/57/7.4/3.8/8.3/q56/.56/3kqbr5 b - 38";
+        let mut pos = P12::default();
+        pos.set_sfen(legacy).expect("failed to parse SFEN string");
+        assert_eq!(pos.generate_sfen(), legacy);
+        let mut pos = P12::default();
+        pos.set_sfen(short).expect("failed to parse SFEN string");
+        assert_eq!(pos.generate_sfen(), legacy);
+        assert!((pos.player_bb(Color::NoColor) & &C2).is_any());
+        for bad in ["2_3", "2_LN", "2_/"] {
+            let sfen = short.replacen("2_N", bad, 1);
+            assert_eq!(pos.set_sfen(&sfen), Err(SfenError::IllegalBoardState));
+        }
+    }
+
+    #[test]
+    fn place_many() {
+        setup();
+        let p = |c| Piece::from_sfen(c).unwrap();
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w KQkr 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.place_many(&[(p('K'), D1), (p('k'), F12), (p('Q'), E1)]),
+            Ok(())
+        );
+        assert_eq!(pos.side_to_move(), Color::Black);
+        assert_eq!(
+            pos.place_many(&[(p('r'), A12), (p('r'), B12)]),
+            Err((1, p('r'), B12))
+        );
+        assert_eq!(pos.piece_at(A12), &Some(p('r')));
+        assert_eq!(pos.stage(), GameStage::Fight);
+    }
+
+    #[test]
+    fn place_detailed() {
+        setup();
+        let p = |c| Piece::from_sfen(c).unwrap();
+        let mut pos = P12::default();
+        pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w KQNkr 1")
+            .expect("failed to parse SFEN string");
+        let keys = |squares: &HashMap<PieceType, _>| {
+            squares
+                .keys()
+                .copied()
+                .sorted_by_key(|pt| pt.index())
+                .collect_vec()
+        };
+        let (_, squares) = pos.place_detailed(p('K'), D1).unwrap();
+        assert_eq!(keys(&squares), [PieceType::King]);
+        assert_eq!(
+            squares[&PieceType::King].len(),
+            pos.empty_squares(p('k')).len()
+        );
+        let (_, squares) = pos.place_detailed(p('k'), F12).unwrap();
+        assert_eq!(keys(&squares), [PieceType::Queen, PieceType::Knight]);
+        pos.place(p('Q'), E1).unwrap();
+        let (_, squares) = pos.place_detailed(p('r'), A12).unwrap();
+        assert_eq!(pos.side_to_move(), Color::White);
+        assert_eq!(keys(&squares), [PieceType::Knight]);
+        let (_, squares) = pos.place_detailed(p('N'), A1).unwrap();
+        assert!(squares.is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        setup();
+        let mut old = P12::new();
+        old.set_sfen("K56/57/57/57/R56/57/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        let mut new = P12::new();
+        new.set_sfen("K56/57/57/57/1R55/L056/57/57/57/57/57/r55k w - 1")
+            .expect("failed to parse SFEN string");
+        let rook = Piece::from_sfen('R');
+        let diff = old.diff(&new);
+        assert_eq!(diff.pieces, [(A5, rook, None), (B5, None, rook)]);
+        assert_eq!(diff.plinths, [A6]);
+        assert_eq!(new.diff(&old).plinths, [A6]);
+        let diff = old.diff(&old.clone());
+        assert!(diff.pieces.is_empty() && diff.plinths.is_empty());
+    }
+
+    #[test]
+    fn starting_position() {
+        setup();
+        let pos = P12::starting_position(Variant::ShuuroFairy)
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.stage(), GameStage::Selection);
+        assert_eq!(pos.variant(), Variant::ShuuroFairy);
+        assert!(pos.occupied_bb().is_empty());
+        assert!(P12::starting_position(Variant::Standard).is_err());
+    }
+
+    #[test]
+    fn mirror_files_plinth() {
+        setup();
+        let mut pos = P12::default();
+        pos.set_sfen("K1LN9/57/57/57/57/57/57/57/57/57/57/L0k55 w - 1")
+            .expect("failed to parse SFEN string");
+        let mirrored = pos.mirror_files();
+        assert_eq!(
+            mirrored.generate_sfen(),
+            "9LN1K/57/57/57/57/57/57/57/57/57/57/55kL0 w - 1"
+        );
+        let mut parsed = P12::default();
+        parsed
+            .set_sfen(&mirrored.generate_sfen())
+            .expect("failed to parse SFEN string");
+        assert_eq!(parsed.generate_sfen(), mirrored.generate_sfen());
+    }
+
     #[test]
     fn generate_sfen() {
         setup();
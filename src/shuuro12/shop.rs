@@ -2,7 +2,8 @@
 mod tests {
 
     use crate::{
-        shuuro12::square12::Square12, Color, Move, Piece, PieceType, Shop,
+        shuuro12::square12::Square12, Color, Move, Piece, PieceType, SfenError,
+        Shop,
     };
 
     #[test]
@@ -33,6 +34,74 @@ mod tests {
         assert!(shop.is_confirmed(Color::White));
     }
 
+    #[test]
+    fn can_buy() {
+        let mut shop = Shop::<Square12>::default();
+        let piece = |piece_type| Piece {
+            piece_type,
+            color: Color::White,
+        };
+        assert!(!shop.can_buy(piece(PieceType::Chancellor)));
+        assert!(!shop.can_buy(Piece {
+            piece_type: PieceType::Queen,
+            color: Color::NoColor
+        }));
+        for _ in 0..3 {
+            assert!(shop.can_buy(piece(PieceType::Queen)));
+            shop.play(Move::Buy {
+                piece: piece(PieceType::Queen),
+            });
+        }
+        assert!(!shop.can_buy(piece(PieceType::Queen)));
+        for _ in 0..6 {
+            shop.play(Move::Buy {
+                piece: piece(PieceType::Rook),
+            });
+        }
+        assert_eq!(shop.credit(Color::White), 50);
+        assert!(!shop.can_buy(piece(PieceType::Rook)));
+        assert!(shop.can_buy(piece(PieceType::Bishop)));
+        shop.confirm(Color::White);
+        assert!(!shop.can_buy(piece(PieceType::Pawn)));
+    }
+
+    #[test]
+    fn undo_select() {
+        let mut shop = Shop::<Square12>::default();
+        let piece = |piece_type| Piece {
+            piece_type,
+            color: Color::White,
+        };
+        for pt in [PieceType::Queen, PieceType::Pawn, PieceType::Queen] {
+            shop.play(Move::Buy { piece: piece(pt) });
+        }
+        assert_eq!(
+            shop.selection_history(Color::White),
+            [
+                PieceType::King,
+                PieceType::Queen,
+                PieceType::Pawn,
+                PieceType::Queen
+            ]
+        );
+        assert_eq!(shop.selection_history(Color::Black), [PieceType::King]);
+        assert_eq!(shop.undo_select(Color::White), Some(PieceType::Queen));
+        assert_eq!(shop.get(piece(PieceType::Queen)), 1);
+        assert_eq!(shop.credit(Color::White), 800 - 120);
+        assert_eq!(shop.undo_select(Color::White), Some(PieceType::Pawn));
+        assert_eq!(shop.undo_select(Color::White), Some(PieceType::Queen));
+        assert_eq!(shop.undo_select(Color::White), None);
+        assert_eq!(shop.credit(Color::White), 800);
+        assert_eq!(shop.selection_history(Color::White), [PieceType::King]);
+        assert_eq!(shop.get_sfen_history(&Color::White).len(), 2);
+
+        shop.play(Move::Buy {
+            piece: piece(PieceType::Queen),
+        });
+        shop.confirm(Color::White);
+        assert_eq!(shop.undo_select(Color::White), None);
+    }
+
     #[test]
     fn set_hand() {
         let cases = [
@@ -46,4 +115,31 @@ mod tests {
             assert_eq!(shop.to_sfen(case.1, true), case.3);
         }
     }
+
+    #[test]
+    fn export_import() {
+        let mut shop = Shop::<Square12>::default();
+        shop.set_hand("RRPPnnnQQ");
+        let exported = shop.export();
+        assert_eq!(exported, "shuuro K2Q2R2P k3n");
+        let mut imported = Shop::<Square12>::default();
+        imported
+            .import(&exported)
+            .expect("failed to import selection");
+        assert_eq!(imported.export(), exported);
+        assert_eq!(imported.credit(Color::White), 800 - 380);
+        assert_eq!(imported.credit(Color::Black), 800 - 120);
+
+        let cases = [
+            ("shuuro K8Q k", SfenError::IllegalSelection),
+            ("shuuro Kk", SfenError::IllegalPieceType),
+            ("shuuro K", SfenError::MissingDataFields),
+            ("garbage K k", SfenError::UnknownVariant),
+            ("shuuro K k 1", SfenError::TooManyDataFields),
+        ];
+        for (selection, error) in cases {
+            assert_eq!(imported.import(selection), Err(error));
+        }
+        assert_eq!(imported.export(), exported);
+    }
 }
@@ -1,12 +1,14 @@
 use std::{cmp::Ordering, fmt::Display};
 
-use crate::{Color, Square};
+use crate::{Color, SfenError, Square};
 
 const ASCII_1: u8 = 1;
 const _ASCII_12: u8 = 12;
 const ASCII_LOWER_A: u8 = b'a';
 const ASCII_LOWER_L: u8 = b'l';
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
 #[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Hash)]
 pub struct Square12 {
     inner: u8,
@@ -42,6 +44,20 @@ impl Iterator for SquareIter {
     }
 }
 
+impl TryFrom<u8> for Square12 {
+    type Error = SfenError;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(SfenError::IllegalPieceFound)
+    }
+}
+
+impl From<Square12> for u8 {
+    fn from(sq: Square12) -> u8 {
+        sq.inner
+    }
+}
+
 impl Display for Square12 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", (self.file() + ASCII_LOWER_A) as char, {
@@ -180,6 +196,18 @@ pub mod consts {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let sq = Square12::from_index(13).unwrap();
+        let json = serde_json::to_string(&sq).expect("failed to serialize");
+        assert_eq!(json, "13");
+        assert_eq!(serde_json::from_str::<Square12>(&json).ok(), Some(sq));
+        assert!(serde_json::from_str::<Square12>("143").is_ok());
+        assert!(serde_json::from_str::<Square12>("144").is_err());
+        assert!(serde_json::from_str::<Square12>("200").is_err());
+    }
+
     #[test]
     fn new() {
         for file in 0..12 {
@@ -230,6 +258,9 @@ mod tests {
         }
 
         assert!(Square12::from_index(145).is_none());
+        assert!(Square12::from_index(144).is_none());
+        assert_eq!(Square12::all().count(), 144);
+        assert!(Square12::all().enumerate().all(|(i, sq)| sq.index() == i));
     }
 
     #[test]
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Once};
 
 pub use crate::attacks::Attacks;
 use crate::{attacks::Ray, bitboard::BitBoard, Color, PieceType, Square};
@@ -6,6 +6,7 @@ use crate::{attacks::Ray, bitboard::BitBoard, Color, PieceType, Square};
 use super::{
     bitboard8::{BB8, SQUARE_BB},
     board_defs::{FILE_BB, RANK_BB},
+    magic8,
     square8::Square8,
 };
 
@@ -66,6 +67,8 @@ pub static mut RAYS: [[BB8<Square8>; 64]; 8] = [[BB8::new(0); 64]; 8];
 
 static mut BETWEEN_BB: [[BB8<Square8>; 64]; 64] = [[BB8::new(0); 64]; 64];
 
+static INIT: Once = Once::new();
+
 #[derive(Clone)]
 pub struct Attacks8<S, B>
 where
@@ -83,6 +86,26 @@ impl Attacks8<Square8, BB8<Square8>> {
             _s: PhantomData,
         }
     }
+
+    /// Rook attacks computed by scanning rays.
+    fn ray_rook_attacks(square: usize, blockers: u64) -> u64 {
+        let blockers = BB8::new(blockers);
+        (Self::get_positive_ray_attacks(Ray::North, square, blockers)
+            | &Self::get_positive_ray_attacks(Ray::East, square, blockers)
+            | &Self::get_negative_ray_attacks(Ray::South, square, blockers)
+            | &Self::get_negative_ray_attacks(Ray::West, square, blockers))
+            .0
+    }
+
+    /// Bishop attacks computed by scanning rays.
+    fn ray_bishop_attacks(square: usize, blockers: u64) -> u64 {
+        let blockers = BB8::new(blockers);
+        (Self::get_positive_ray_attacks(Ray::NorthEast, square, blockers)
+            | &Self::get_positive_ray_attacks(Ray::NorthWest, square, blockers)
+            | &Self::get_negative_ray_attacks(Ray::SouthEast, square, blockers)
+            | &Self::get_negative_ray_attacks(Ray::SouthWest, square, blockers))
+            .0
+    }
 }
 
 impl Default for Attacks8<Square8, BB8<Square8>> {
@@ -119,7 +142,24 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
         }
     }
 
-    fn init_quick() {}
+    fn init_quick() {
+        magic8::init(Self::ray_rook_attacks, Self::ray_bishop_attacks);
+    }
+
+    fn init() {
+        INIT.call_once(Self::init_tables);
+    }
+
+    fn get_bishop_attacks(
+        square: usize,
+        blockers: BB8<Square8>,
+    ) -> BB8<Square8> {
+        BB8::new(magic8::bishop_attacks(square, blockers.0))
+    }
+
+    fn get_rook_attacks(square: usize, blockers: BB8<Square8>) -> BB8<Square8> {
+        BB8::new(magic8::rook_attacks(square, blockers.0))
+    }
 
     fn init_north_ray() {
         let empty = &BB8::empty();
@@ -184,9 +224,9 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
     }
 
     fn init_north_east_ray() {
-        let delta = &[13];
+        let delta = &[9];
         for sq in Square8::iter() {
-            let bb = diagonal_ray(sq.index() as i32, delta, 13);
+            let bb = diagonal_ray(sq.index() as i32, delta, 9);
             unsafe {
                 RAYS[Ray::NorthEast as usize][sq.index()] = bb;
             }
@@ -194,9 +234,9 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
     }
 
     fn init_north_west_ray() {
-        let delta = &[11];
+        let delta = &[7];
         for sq in Square8::iter() {
-            let bb = diagonal_ray(sq.index() as i32, delta, 11);
+            let bb = diagonal_ray(sq.index() as i32, delta, 7);
             unsafe {
                 RAYS[Ray::NorthWest as usize][sq.index()] = bb;
             }
@@ -204,9 +244,9 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
     }
 
     fn init_south_east_ray() {
-        let delta = &[-11];
+        let delta = &[-7];
         for sq in Square8::iter() {
-            let bb = diagonal_ray(sq.index() as i32, delta, 11);
+            let bb = diagonal_ray(sq.index() as i32, delta, -7);
             unsafe {
                 RAYS[Ray::SouthEast as usize][sq.index()] = bb;
             }
@@ -214,9 +254,9 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
     }
 
     fn init_south_west_ray() {
-        let delta = &[-11];
+        let delta = &[-9];
         for sq in Square8::iter() {
-            let bb = diagonal_ray(sq.index() as i32, delta, 11);
+            let bb = diagonal_ray(sq.index() as i32, delta, -9);
             unsafe {
                 RAYS[Ray::SouthWest as usize][sq.index()] = bb;
             }
@@ -338,6 +378,10 @@ impl Attacks<Square8, BB8<Square8>> for Attacks8<Square8, BB8<Square8>> {
         unsafe { BETWEEN_BB[square1.index()][square2.index()] }
     }
 
+    fn ray(dir: Ray, sq: &Square8) -> BB8<Square8> {
+        unsafe { RAYS[dir as usize][sq.index()] }
+    }
+
     fn get_pawn_moves(square: usize, color: Color) -> BB8<Square8> {
         unsafe {
             match color {
@@ -367,6 +411,8 @@ fn diagonal_ray(start: i32, delta: &[i32; 1], new_sq: i32) -> BB8<Square8> {
 #[cfg(test)]
 pub mod tests {
 
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
     use crate::{
         attacks::Ray,
         bitboard::BitBoard,
@@ -381,7 +427,7 @@ pub mod tests {
         Color, Square,
     };
 
-    use super::{Attacks, Attacks8, KING_MOVES, PAWN_MOVES, RAYS};
+    use super::{Attacks, Attacks8, BB8, KING_MOVES, PAWN_MOVES, RAYS};
 
     #[test]
     fn pawn_moves() {
@@ -409,7 +455,7 @@ pub mod tests {
                 let bb = PAWN_MOVES[case.2.index()][case.0.index()];
                 let moves = bb & &case.1;
                 assert_eq!(moves.is_any(), case.3);
-                assert_eq!(bb.count(), case.4);
+                assert_eq!(bb.len(), case.4);
             };
         }
     }
@@ -435,14 +481,14 @@ pub mod tests {
                     for attack in case.1.into_iter().flatten() {
                         assert!((attacks & &attack).is_any());
                     }
-                    assert_eq!(attacks.count(), case.2);
+                    assert_eq!(attacks.len(), case.2);
                 }
                 Color::Black => {
                     let attacks = BLACK_PAWN_ATTACKS[sq];
                     for attack in case.1.into_iter().flatten() {
                         assert!((attacks & &attack).is_any());
                     }
-                    assert_eq!(attacks.count(), case.2);
+                    assert_eq!(attacks.len(), case.2);
                 }
                 Color::NoColor => (),
             }
@@ -464,7 +510,7 @@ pub mod tests {
             for sq in case.1 {
                 assert!((attacks & &sq).is_any());
             }
-            assert_eq!(attacks.count(), capacity);
+            assert_eq!(attacks.len() as usize, capacity);
         }
     }
 
@@ -486,6 +532,26 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn magic_attacks() {
+        Attacks8::init();
+        let mut rng = StdRng::seed_from_u64(64);
+        for sq in 0..64 {
+            for _ in 0..200 {
+                let blockers = rng.gen::<u64>() & rng.gen::<u64>();
+                let bb = BB8::new(blockers);
+                assert_eq!(
+                    Attacks8::get_rook_attacks(sq, bb).0,
+                    Attacks8::ray_rook_attacks(sq, blockers)
+                );
+                assert_eq!(
+                    Attacks8::get_bishop_attacks(sq, bb).0,
+                    Attacks8::ray_bishop_attacks(sq, blockers)
+                );
+            }
+        }
+    }
+
     #[test]
     fn rays() {
         Attacks8::init();
@@ -502,6 +568,12 @@ pub mod tests {
             (E8, E3, 4, Ray::South),
             (B8, B6, 1, Ray::South),
             (F7, F4, 2, Ray::South),
+            (A1, H8, 6, Ray::NorthEast),
+            (C3, E5, 1, Ray::NorthEast),
+            (H1, A8, 6, Ray::NorthWest),
+            (A8, H1, 6, Ray::SouthEast),
+            (H8, A1, 6, Ray::SouthWest),
+            (E5, C3, 1, Ray::SouthWest),
         ];
 
         for case in ok_cases {
@@ -509,7 +581,7 @@ pub mod tests {
                 let ray = &RAYS[case.3 as usize][case.0.index()];
                 let between = Attacks8::between(case.0, case.1);
                 let calc = *ray & &between;
-                assert_eq!(calc.count(), case.2);
+                assert_eq!(calc.len(), case.2);
             }
         }
     }
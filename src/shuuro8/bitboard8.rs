@@ -8,7 +8,7 @@ use std::ops::{
     BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not,
 };
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct BB8<S: Square>(pub u64, PhantomData<S>);
 
 impl BB8<Square8> {
@@ -199,7 +199,9 @@ impl BitBoard<Square8> for BB8<Square8> {
         } else {
             let calc = self.0.trailing_zeros() as u64;
             let sq = Square8::from_index(calc as u8);
-            self.0 = calc;
+            if let Some(sq) = sq {
+                self.clear_at(sq);
+            }
             sq
         }
     }
@@ -237,3 +239,20 @@ pub const SQUARE_BB: [BB8<Square8>; 64] = {
     }
     squares
 };
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        bitboard::BitBoard,
+        shuuro8::{bitboard8::square_bb, square8::consts::*},
+    };
+
+    #[test]
+    fn pop() {
+        let mut bb = square_bb(&B2) | &E5 | &H8;
+        assert_eq!(bb.pop(), Some(B2));
+        assert_eq!(bb.pop(), Some(E5));
+        assert_eq!(bb.pop(), Some(H8));
+        assert_eq!(bb.pop(), None);
+    }
+}
@@ -0,0 +1,247 @@
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const RANK_1: u64 = 0xFF;
+const RANK_8: u64 = RANK_1 << 56;
+
+const ROOK_TABLE_SIZE: usize = 102_400;
+const BISHOP_TABLE_SIZE: usize = 5_248;
+
+const ROOK_MAGICS: [u64; 64] = [
+    0x0080_0440_0170_8020,
+    0x0040_4000_2000_1000,
+    0x2A00_2180_4010_0A00,
+    0x2080_0480_0801_1000,
+    0x0300_1008_002B_0004,
+    0xA200_0104_0200_1008,
+    0x0400_0421_0802_2090,
+    0x8200_0040_2205_8403,
+    0x0880_8020_8000_4001,
+    0x8202_4000_2010_0049,
+    0x0004_8020_0182_1000,
+    0x0020_8008_0084_1002,
+    0x1420_8008_0004_0082,
+    0x2200_8004_0002_0080,
+    0x2804_0041_0408_2210,
+    0x0820_801C_4080_0100,
+    0x8240_8080_1122_C000,
+    0x0000_40C0_1001_2000,
+    0x0060_8080_1000_2000,
+    0x0000_8080_0800_1001,
+    0x0000_8080_0800_0400,
+    0x0106_8080_0200_8400,
+    0x0240_4400_0201_0850,
+    0x0040_0200_00A1_0044,
+    0x0000_4008_8000_8034,
+    0x00C0_2001_8040_0084,
+    0x2090_0010_8020_0081,
+    0x0808_0800_8010_0082,
+    0x0010_0800_8004_0080,
+    0x0000_0200_8080_0400,
+    0x8011_0200_8080_0100,
+    0x0000_0042_0000_8104,
+    0x0300_4000_8180_0064,
+    0x0030_1040_0040_2000,
+    0x4000_1000_8280_2004,
+    0x10A0_1001_0100_2008,
+    0x0000_8004_0080_0800,
+    0x2007_8042_0080_0400,
+    0x0028_4810_2400_0221,
+    0x0A00_8000_6080_0100,
+    0x1440_8000_4000_8020,
+    0x0A02_0048_8902_0020,
+    0x0201_00C1_2003_0018,
+    0x0150_2200_4012_0009,
+    0x0028_0010_0901_0004,
+    0x2002_0200_0400_8080,
+    0x00C8_0100_0200_8080,
+    0x4001_0400_488A_0021,
+    0x00C0_8001_0042_2300,
+    0x2402_8020_0040_0980,
+    0x0000_8040_2A00_1200,
+    0x0000_1000_0800_8080,
+    0x0000_0400_0800_8080,
+    0x1081_5044_6040_0801,
+    0x1404_1008_2241_0400,
+    0x1002_2920_4084_1200,
+    0x8844_4011_0208_8222,
+    0x8020_1020_8108_4001,
+    0x4081_0044_1008_2001,
+    0x0670_6049_5000_0501,
+    0x1002_0048_1020_1C16,
+    0x0C02_0001_1004_0882,
+    0x8000_2490_0801_5204,
+    0x0466_0020_4084_0102,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0904_1000_A801_0048,
+    0x0202_5004_0482_9006,
+    0x0084_412A_0204_0124,
+    0x0488_2042_4134_0C11,
+    0x8072_0210_4000_2104,
+    0x0806_1804_0404_0000,
+    0x0000_4210_1088_0058,
+    0x2500_8024_0220_0400,
+    0x0008_4044_8400_AA00,
+    0x0004_8808_2084_0054,
+    0x0000_1004_4040_4101,
+    0x4800_9441_0020_8010,
+    0x0501_C710_4002_0800,
+    0x0820_0202_1004_0150,
+    0x8208_4084_1008_0400,
+    0x0000_4047_0801_9000,
+    0x0090_0060_6045_0512,
+    0x0404_8020_0102_1200,
+    0x0030_0004_4052_0040,
+    0x0008_0000_8283_0100,
+    0x8044_2022_0201_000A,
+    0x0002_0000_2201_2020,
+    0x0014_0800_4402_880A,
+    0x0000_2111_4424_0C12,
+    0x1410_1004_0644_1004,
+    0x1202_0890_2048_0C90,
+    0x0408_0800_1044_4040,
+    0x8208_0800_0022_0020,
+    0x0121_0010_0300_4001,
+    0x0111_0502_0200_4100,
+    0x0248_C606_1088_0404,
+    0x0005_0202_0046_00A2,
+    0x0004_10C4_C208_A003,
+    0x8002_0805_0020_0100,
+    0x000A_01D0_0831_0100,
+    0x5040_1010_100C_0402,
+    0x6122_1004_0001_0408,
+    0x000C_0800_8842_00A0,
+    0x2308_0200_8404_00E0,
+    0x8108_4301_0010_2480,
+    0x0008_9010_1000_4800,
+    0x0406_00D0_0841_0C40,
+    0x1406_0104_1102_0200,
+    0x8000_1040_1040_4202,
+    0x5001_C040_9200_AD00,
+    0x0534_0800_8900_8808,
+    0x1084_0400_9A00_4400,
+    0x2008_00A1_0200_0844,
+    0x5008_9C01_18C0_0440,
+    0x03C4_2104_1044_0052,
+    0x0448_0044_0404_1001,
+    0x0800_2000_4202_0040,
+    0x4008_D012_0202_0800,
+    0x080C_4048_220C_2086,
+    0x0104_0404_8802_0000,
+    0x4014_81A8_0200_8000,
+    0x0010_4404_0402_0204,
+    0x0800_0084_0101_1000,
+    0x00C1_0082_0084_1103,
+    0x8001_8608_0042_0200,
+    0x6208_1004_A410_5408,
+    0x8008_1420_2042_1080,
+    0x0000_4028_0208_C200,
+    0x0020_0101_0200_8200,
+];
+
+#[derive(Clone, Copy)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    const fn empty() -> Self {
+        Magic {
+            mask: 0,
+            magic: 0,
+            shift: 0,
+            offset: 0,
+        }
+    }
+
+    fn index(&self, occupied: u64) -> usize {
+        self.offset
+            + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift)
+                as usize
+    }
+}
+
+static mut ROOK: [Magic; 64] = [Magic::empty(); 64];
+static mut BISHOP: [Magic; 64] = [Magic::empty(); 64];
+static mut ROOK_TABLE: [u64; ROOK_TABLE_SIZE] = [0; ROOK_TABLE_SIZE];
+static mut BISHOP_TABLE: [u64; BISHOP_TABLE_SIZE] = [0; BISHOP_TABLE_SIZE];
+
+/// Fills attack tables, using ray attacks as reference.
+/// Must be called after rays are initialized.
+pub(crate) fn init(rook: fn(usize, u64) -> u64, bishop: fn(usize, u64) -> u64) {
+    let edges = FILE_A | FILE_H | RANK_1 | RANK_8;
+    let mut rook_offset = 0;
+    let mut bishop_offset = 0;
+    for sq in 0..64 {
+        let file = FILE_A << (sq % 8);
+        let rank = RANK_1 << (sq / 8 * 8);
+        let empty = rook(sq, 0);
+        let mask = ((empty & rank) & !(FILE_A | FILE_H))
+            | ((empty & file) & !(RANK_1 | RANK_8));
+        let magic = Magic {
+            mask,
+            magic: ROOK_MAGICS[sq],
+            shift: 64 - mask.count_ones(),
+            offset: rook_offset,
+        };
+        rook_offset += 1 << mask.count_ones();
+        for occupied in subsets(mask) {
+            unsafe {
+                ROOK_TABLE[magic.index(occupied)] = rook(sq, occupied);
+            }
+        }
+        unsafe {
+            ROOK[sq] = magic;
+        }
+
+        let mask = bishop(sq, 0) & !edges;
+        let magic = Magic {
+            mask,
+            magic: BISHOP_MAGICS[sq],
+            shift: 64 - mask.count_ones(),
+            offset: bishop_offset,
+        };
+        bishop_offset += 1 << mask.count_ones();
+        for occupied in subsets(mask) {
+            unsafe {
+                BISHOP_TABLE[magic.index(occupied)] = bishop(sq, occupied);
+            }
+        }
+        unsafe {
+            BISHOP[sq] = magic;
+        }
+    }
+}
+
+/// Returns all subsets of mask.
+fn subsets(mask: u64) -> Vec<u64> {
+    let mut all = Vec::with_capacity(1 << mask.count_ones());
+    let mut subset = 0_u64;
+    loop {
+        all.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            return all;
+        }
+    }
+}
+
+/// Returns rook attacks for square with given blockers.
+pub(crate) fn rook_attacks(sq: usize, occupied: u64) -> u64 {
+    unsafe {
+        let magic = ROOK[sq];
+        ROOK_TABLE[magic.index(occupied)]
+    }
+}
+
+/// Returns bishop attacks for square with given blockers.
+pub(crate) fn bishop_attacks(sq: usize, occupied: u64) -> u64 {
+    unsafe {
+        let magic = BISHOP[sq];
+        BISHOP_TABLE[magic.index(occupied)]
+    }
+}
@@ -1,6 +1,9 @@
 pub mod attacks8;
 pub mod bitboard8;
 pub mod board_defs;
+mod magic8;
 pub mod plinths_set8;
 pub mod position8;
 pub mod square8;
+
+pub use board_defs::{FILE_BB, RANK_BB};
@@ -1,8 +1,12 @@
 use crate::{
-    attacks::Attacks, bitboard::BitBoard, plinths_set::PlinthGen, Square,
+    attacks::Attacks,
+    bitboard::BitBoard,
+    plinths_set::{PlinthGen, PlinthRng},
+    Square,
 };
 
 use super::{attacks8::Attacks8, bitboard8::BB8, square8::Square8};
+use rand::{thread_rng, Rng};
 use std::marker::PhantomData;
 
 pub struct PlinthGen8<S, B>
@@ -31,8 +35,13 @@ impl PlinthGen<Square8, BB8<Square8>> for PlinthGen8<Square8, BB8<Square8>> {
 
 impl PlinthGen8<Square8, BB8<Square8>> {
     pub fn start(&self) -> BB8<Square8> {
+        self.start_with(&mut PlinthRng::new(thread_rng().gen()))
+    }
+
+    /// Generate plinths using given random number generator.
+    pub fn start_with(&self, rng: &mut PlinthRng) -> BB8<Square8> {
         let sections = [(2, 4, 0, 8, 2), (4, 6, 0, 8, 2)];
-        self.generate_plinths(&sections)
+        self.generate_plinths(&sections, rng)
     }
 }
 
@@ -47,7 +56,9 @@ impl Default for PlinthGen8<Square8, BB8<Square8>> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{attacks::Attacks, shuuro8::attacks8::Attacks8};
+    use crate::{
+        attacks::Attacks, bitboard::BitBoard, shuuro8::attacks8::Attacks8,
+    };
 
     use super::PlinthGen8;
 
@@ -56,6 +67,6 @@ mod tests {
         Attacks8::init();
         let pl = PlinthGen8::default();
         let b = pl.start();
-        assert_eq!(b.count(), 4);
+        assert_eq!(b.len(), 4);
     }
 }
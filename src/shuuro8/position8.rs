@@ -1,9 +1,13 @@
 use std::{fmt, marker::PhantomData};
 
+#[cfg(feature = "serde")]
+use crate::position::PositionData;
 use crate::{
     bitboard::BitBoard,
-    position::{Board, Outcome, Placement, Play, Position, Rules, Sfen},
-    Color, Hand, Move, MoveData, Piece, PieceType, SfenError, Square, Variant,
+    plinths_set::PlinthRng,
+    position::{Board, Clock, Outcome, Placement, Play, Position, Rules, Sfen},
+    zobrist, Color, Hand, Move, MoveData, Piece, PieceType, SfenError, Square,
+    Variant,
 };
 
 use super::{
@@ -34,11 +38,44 @@ where
     color_bb: [BB8<Square8>; 3],
     game_status: Outcome,
     variant: Variant,
+    auto_draw_repetition: bool,
+    auto_draw_material: bool,
+    halfmove_clock: u16,
+    record_history: bool,
+    zobrist: u64,
+    clock: Option<Clock>,
+    castling_rights: [bool; 4],
     pub type_bb: [BB8<Square8>; 10],
     _a: PhantomData<B>,
     _s: PhantomData<S>,
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for P8<Square8, BB8<Square8>> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error> {
+        PositionData::new::<
+            BB8<Square8>,
+            Attacks8<Square8, BB8<Square8>>,
+            Self,
+        >(self)
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for P8<Square8, BB8<Square8>> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        PositionData::<Square8>::deserialize(deserializer)?
+            .restore::<BB8<Square8>, Attacks8<Square8, BB8<Square8>>, Self>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
     for P8<Square8, BB8<Square8>>
 {
@@ -47,6 +84,12 @@ impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
     }
 
     fn set_piece(&mut self, sq: Square8, p: Option<Piece>) {
+        if let Some(old) = self.board.get(sq) {
+            self.zobrist ^= zobrist::piece_key(*old, sq.index());
+        }
+        if let Some(p) = p {
+            self.zobrist ^= zobrist::piece_key(p, sq.index());
+        }
         self.board.set(sq, p)
     }
 
@@ -103,6 +146,10 @@ impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
         self.ply += 1;
     }
 
+    fn decrement_ply(&mut self) {
+        self.ply = self.ply.saturating_sub(1);
+    }
+
     fn flip_side_to_move(&mut self) {
         self.side_to_move = self.side_to_move.flip();
     }
@@ -127,6 +174,78 @@ impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
         self.variant = variant;
     }
 
+    fn auto_draw_repetition(&self) -> bool {
+        self.auto_draw_repetition
+    }
+
+    fn auto_draw_material(&self) -> bool {
+        self.auto_draw_material
+    }
+
+    fn update_auto_draw_repetition(&mut self, enabled: bool) {
+        self.auto_draw_repetition = enabled;
+    }
+
+    fn update_auto_draw_material(&mut self, enabled: bool) {
+        self.auto_draw_material = enabled;
+    }
+
+    fn clock(&self) -> Option<Clock> {
+        self.clock
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    fn record_history(&self) -> bool {
+        self.record_history
+    }
+
+    fn clone_for_search(&self) -> Self {
+        P8 {
+            board: self.board.clone(),
+            hand: self.hand.clone(),
+            ply: self.ply,
+            side_to_move: self.side_to_move,
+            move_history: Vec::new(),
+            occupied_bb: self.occupied_bb,
+            color_bb: self.color_bb,
+            game_status: self.game_status.clone(),
+            variant: self.variant,
+            auto_draw_repetition: self.auto_draw_repetition,
+            auto_draw_material: self.auto_draw_material,
+            halfmove_clock: self.halfmove_clock,
+            castling_rights: self.castling_rights,
+            type_bb: self.type_bb,
+            record_history: false,
+            zobrist: self.zobrist,
+            clock: self.clock,
+            _a: PhantomData,
+            _s: PhantomData,
+        }
+    }
+
+    fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    fn update_halfmove_clock(&mut self, clock: u16) {
+        self.halfmove_clock = clock;
+    }
+
+    fn castling_rights(&self) -> [bool; 4] {
+        self.castling_rights
+    }
+
+    fn update_castling_rights(&mut self, rights: [bool; 4]) {
+        self.castling_rights = rights;
+    }
+
     fn insert_sfen(&mut self, sfen: Move<Square8>) {
         self.move_history.push(sfen);
     }
@@ -135,6 +254,10 @@ impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
         self.move_history.push(move_record)
     }
 
+    fn pop_move(&mut self) -> Option<Move<Square8>> {
+        self.move_history.pop()
+    }
+
     fn clear_sfen_history(&mut self) {
         self.move_history.clear();
     }
@@ -180,6 +303,14 @@ impl Board<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
     fn dimensions(&self) -> u8 {
         8
     }
+
+    fn file(&self, f: u8) -> Option<BB8<Square8>> {
+        FILE_BB.get(f as usize).copied()
+    }
+
+    fn rank(&self, r: u8) -> Option<BB8<Square8>> {
+        RANK_BB.get(r as usize).copied()
+    }
 }
 
 impl Sfen<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
@@ -213,8 +344,14 @@ impl Sfen<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
 impl Placement<Square8, BB8<Square8>, Attacks8<Square8, BB8<Square8>>>
     for P8<Square8, BB8<Square8>>
 {
-    fn generate_plinths(&mut self) {
-        self.color_bb[Color::NoColor.index()] = PlinthGen8::default().start();
+    fn generate_plinths_seeded(&mut self, seed: u64) {
+        let mut rng = PlinthRng::new(seed);
+        self.color_bb[Color::NoColor.index()] =
+            PlinthGen8::default().start_with(&mut rng);
+    }
+
+    fn plinth_sections(&self) -> Vec<(BB8<Square8>, u32)> {
+        vec![(RANK_BB[2] | &RANK_BB[3], 2), (RANK_BB[4] | &RANK_BB[5], 2)]
     }
 
     fn white_placement_attacked_ranks(&self) -> BB8<Square8> {
@@ -346,6 +483,13 @@ impl Default for P8<Square8, BB8<Square8>> {
             type_bb: Default::default(),
             game_status: Outcome::MoveOk,
             variant: Variant::Standard,
+            auto_draw_repetition: true,
+            auto_draw_material: true,
+            halfmove_clock: 0,
+            record_history: true,
+            zobrist: 0,
+            clock: None,
+            castling_rights: [false; 4],
             _a: PhantomData,
             _s: PhantomData,
         }
@@ -422,3 +566,670 @@ impl fmt::Display for P8<Square8, BB8<Square8>> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod position_tests {
+
+    use itertools::Itertools;
+
+    use crate::{
+        attacks::Attacks,
+        bitboard::BitBoard,
+        position::{
+            Board, Clock, Outcome, Placement, Play, Position, Rules, Sfen,
+        },
+        shuuro8::{
+            attacks8::Attacks8,
+            bitboard8::BB8,
+            position8::P8,
+            square8::{consts::*, Square8},
+        },
+        Color, Move, MoveData, MoveError, Piece, PieceType, SfenError, Square,
+        Variant,
+    };
+
+    fn setup() {
+        Attacks8::init();
+    }
+
+    #[test]
+    fn castling() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K2R/8/8/8/8/8/8/r3k2r w - 1 KQkq")
+            .expect("failed to parse SFEN string");
+        let king = *pos.legal_moves(&Color::White).get(&E1).unwrap();
+        assert!((king & &G1).is_any());
+        assert!((king & &C1).is_any());
+        pos.play("e1", "g1").expect("illegal move");
+        assert_eq!(pos.piece_at(G1).unwrap().piece_type, PieceType::King);
+        assert_eq!(pos.piece_at(F1).unwrap().piece_type, PieceType::Rook);
+        assert!(pos.piece_at(H1).is_none());
+        assert_eq!(pos.castling_rights(), [false, false, true, true]);
+        assert!(pos.generate_sfen().ends_with(" kq 1"));
+        let castle = Move::Castle {
+            king_from: E8,
+            king_to: C8,
+            rook_from: A8,
+            rook_to: D8,
+            move_data: MoveData::default(),
+        };
+        assert!(pos.make_move(castle.clone()).is_ok());
+        assert_eq!(
+            pos.move_history().last().map(Move::to_string),
+            Some(castle.to_string())
+        );
+        assert_eq!(castle.format(), "O-O-O");
+        assert!(!pos.generate_sfen().ends_with("kq"));
+        assert!(pos.unmake_move().is_ok());
+        assert_eq!(pos.castling_rights(), [false, false, true, true]);
+        assert_eq!(pos.piece_at(A8).unwrap().piece_type, PieceType::Rook);
+
+        pos.set_sfen("R3K2R/8/8/8/8/8/8/r3k2r w - 1 KQkq 7")
+            .expect("failed to parse SFEN string");
+        for (from, to) in [("e1", "f1"), ("h1", "h2")] {
+            pos.play(from, to).expect("illegal move");
+            assert!(pos.unmake_move().is_ok());
+            assert_eq!(pos.castling_rights(), [true; 4]);
+            assert_eq!(pos.halfmove_clock(), 7);
+        }
+    }
+
+    #[test]
+    fn castling_not_allowed() {
+        setup();
+        let cases = [
+            ("R3K2R/8/8/8/8/8/8/r3kr2 w - 1 KQ", G1, false),
+            ("R3K2R/8/8/8/8/8/8/r3kr2 w - 1 KQ", C1, true),
+            ("R3K1NR/8/8/8/8/8/8/r3k3 w - 1 KQ", G1, false),
+            ("R3K2R/8/8/8/8/8/8/r3k3 w - 1 Q", G1, false),
+            ("R3K2R/8/8/8/8/8/8/r2rk3 w - 1 KQ", C1, false),
+            ("R3K2R/8/8/8/8/8/8/r3rk2 w - 1 KQ", G1, false),
+            ("R3K2R/8/8/8/8/8/8/r3rk2 w - 1 KQ", C1, false),
+            ("R3K2R/2p5/8/8/8/8/8/r3k3 w - 1 KQ", C1, false),
+        ];
+        for (sfen, to, allowed) in cases {
+            let mut pos = P8::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            let king = *pos.legal_moves(&Color::White).get(&E1).unwrap();
+            assert_eq!((king & &to).is_any(), allowed, "{sfen}");
+            let result = pos.make_move(Move::new(E1, to));
+            assert_eq!(result.is_ok(), allowed);
+        }
+    }
+
+    #[test]
+    fn castling_rights() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K2R/8/8/8/8/8/8/r3k2r w - 1 KQkq")
+            .expect("failed to parse SFEN string");
+        pos.play("h1", "h7").expect("illegal move");
+        assert_eq!(pos.castling_rights(), [false, true, true, true]);
+        pos.play("e8", "d8").expect("illegal move");
+        assert_eq!(pos.castling_rights(), [false, true, false, false]);
+        assert!(pos.generate_sfen().ends_with(" Q 2"));
+        assert!(pos.make_move(Move::new(E1, G1)).is_err());
+        pos.set_sfen("R3K2R/8/8/8/8/8/8/r3k2r w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.castling_rights(), [false; 4]);
+        assert_eq!(pos.generate_sfen(), "R3K2R/8/8/8/8/8/8/r3k2r w - 1");
+        assert!(pos.set_sfen("R3K2R/8/8/8/8/8/8/r3k2r w - 1 KX").is_err());
+    }
+
+    #[test]
+    fn to_san() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/8/8/8/1p6/7k/5P2/R7 w - 1")
+            .expect("failed to parse SFEN string");
+        let cases = [
+            (A1, A5, Some("R1a5")),
+            (A1, B1, Some("Rb1")),
+            (E1, E2, Some("Ke2")),
+            (F7, F8, Some("f8=Q+")),
+            (A2, B3, None),
+            (A1, B2, None),
+        ];
+        for (from, to, san) in cases {
+            let san = san.map(String::from);
+            assert_eq!(Move::new(from, to).to_san(&pos), san);
+        }
+        assert_eq!(pos.generate_sfen(), "R3K3/8/8/8/1p6/7k/5P2/R7 w - 1");
+    }
+
+    #[test]
+    fn to_pgn() {
+        setup();
+        let mut pos = P8::default();
+        pos.update_variant(Variant::Standard);
+        pos.set_sfen("R3K3/8/8/8/8/8/8/7k w - 1")
+            .expect("failed to parse SFEN string");
+        pos.play_moves(&["a1_a7", "h8_g8", "e1_e2", "g8_f8", "e2_e3"])
+            .expect("illegal move");
+        assert_eq!(
+            pos.to_pgn(),
+            "[Variant \"standard\"]\n[FEN \"R3K3/8/8/8/8/8/8/7k w - 1\"]\n\
+             [Result \"*\"]\n\n1. Ra7 Kg8 2. Ke2 Kf8 3. Ke3 *"
+        );
+    }
+
+    #[test]
+    fn perft_start_position() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbqkbnr w - 1 KQkq")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.perft(1), 20);
+        assert_eq!(pos.perft(2), 400);
+        assert_eq!(pos.perft(3), 8902);
+    }
+
+    #[test]
+    fn insufficient_material_bishops() {
+        setup();
+        let cases = [
+            ("K7/8/8/8/8/8/8/B1B1k3 w - 1", false),
+            ("K7/8/8/8/8/8/8/B1B1B1k1 w - 1", false),
+            ("K7/8/8/8/8/8/8/BB1B2k1 w - 1", true),
+            ("K7/8/8/8/8/8/8/B1B1Nk2 w - 1", true),
+            ("K7/8/8/8/8/8/8/B1B1k2b w - 1", false),
+        ];
+        for (sfen, sufficient) in cases {
+            let mut pos = P8::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(
+                pos.detect_insufficient_material().is_ok(),
+                sufficient,
+                "{sfen}"
+            );
+        }
+    }
+
+    #[test]
+    fn has_mating_material() {
+        setup();
+        let cases = [
+            ("K7/8/8/8/8/8/8/B1B1k1r1 w - 1", false, true),
+            ("K7/8/8/8/8/8/p7/BB1B2k1 w - 1", true, true),
+            ("K7/8/8/8/8/8/8/N3k3 w - 1", false, false),
+        ];
+        for (sfen, white, black) in cases {
+            let mut pos = P8::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(pos.has_mating_material(Color::White), white, "{sfen}");
+            assert_eq!(pos.has_mating_material(Color::Black), black, "{sfen}");
+        }
+    }
+
+    #[test]
+    fn play_moves() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/8/8/8/8/8/8/7k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.play_moves(&["a1_a7", "h8_g8", "a7_a8"]),
+            Ok(&Outcome::Check {
+                color: Color::Black
+            })
+        );
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/8/8/8/8/8/8/7k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(
+            pos.play_moves(&["a1_a7", "h8_h7", "e1_e2"]),
+            Err((1, SfenError::IllegalMove))
+        );
+        assert_eq!(
+            pos.play_moves(&["e1-e2"]),
+            Err((0, SfenError::IllegalMove))
+        );
+        assert_eq!(pos.move_history().len(), 1);
+    }
+
+    #[test]
+    fn under_promotion() {
+        setup();
+        let sfen = "K7/8/8/8/8/8/P6p/7k w - 1";
+        let mut pos = P8::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        pos.make_move(Move::new_promotion(A7, A8, PieceType::Knight))
+            .expect("illegal move");
+        assert_eq!(pos.piece_at(A8).unwrap().piece_type, PieceType::Knight);
+        assert_eq!(pos.piece_at(A8).unwrap().color, Color::White);
+        let last = pos.move_history().last().unwrap();
+        assert_eq!(last.format(), "a8=N");
+        assert_eq!(last.to_string(), "a7_a8n");
+        assert_eq!(
+            Move::try_from("a7_a8n"),
+            Ok(Move::new_promotion(A7, A8, PieceType::Knight))
+        );
+        assert_eq!(
+            Move::<Square8>::from_sfen("a7_a8n").and_then(|m| m.promotion()),
+            Some(PieceType::Knight)
+        );
+        pos.unmake_move().expect("failed to unmake move");
+        assert_eq!(pos.piece_at(A7).unwrap().piece_type, PieceType::Pawn);
+        assert!(pos.piece_at(A8).is_none());
+
+        for piece_type in
+            [PieceType::King, PieceType::Pawn, PieceType::Chancellor]
+        {
+            assert!(pos
+                .make_move(Move::new_promotion(A7, A8, piece_type))
+                .is_err());
+        }
+        pos.update_variant(Variant::StandardFairy);
+        pos.make_move(Move::new_promotion(A7, A8, PieceType::Chancellor))
+            .expect("illegal move");
+        assert_eq!(pos.piece_at(A8).unwrap().piece_type, PieceType::Chancellor);
+
+        let mut pos = P8::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        pos.make_move(Move::new(A7, A8)).expect("illegal move");
+        assert_eq!(pos.piece_at(A8).unwrap().piece_type, PieceType::Queen);
+    }
+
+    #[test]
+    fn under_promotion_sfen() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("KRRRRRR1/8/8/8/8/8/P7/7k w - 1")
+            .expect("failed to parse SFEN string");
+        pos.make_move(Move::new_promotion(A7, A8, PieceType::Rook))
+            .expect("illegal move");
+        let sfen = pos.generate_sfen();
+        let mut parsed = P8::default();
+        parsed.set_sfen(&sfen).expect("failed to parse SFEN string");
+        assert_eq!(parsed.generate_sfen(), sfen);
+        assert_eq!(parsed.piece_at(A8).unwrap().piece_type, PieceType::Rook);
+        assert_eq!(
+            parsed.set_sfen("KRRRRRRR/PPPPPPPP/PPPP4/8/8/8/8/7k w - 1"),
+            Err(SfenError::IllegalBoardState)
+        );
+    }
+
+    #[test]
+    fn all_moves_promotions() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("K7/8/8/8/8/8/P6p/7k w - 1")
+            .expect("failed to parse SFEN string");
+        let promotions = pos
+            .all_moves(Color::White)
+            .iter()
+            .filter(|m| m.info() == Some((A7, A8)))
+            .map(|m| m.promotion())
+            .collect_vec();
+        assert_eq!(
+            promotions,
+            [
+                Some(PieceType::Queen),
+                Some(PieceType::Rook),
+                Some(PieceType::Bishop),
+                Some(PieceType::Knight)
+            ]
+        );
+        assert_eq!(pos.children(Color::White).len(), 7);
+        pos.update_variant(Variant::StandardFairy);
+        assert_eq!(pos.all_moves(Color::White).len(), 10);
+    }
+
+    #[test]
+    fn zobrist_hash() {
+        setup();
+        let sfen = "RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbqkbnr w - 1 KQkq";
+        let mut pos = P8::default();
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        let start = pos.hash();
+        pos.play_moves(&["g1_f3", "g8_f6", "b1_c3"])
+            .expect("illegal move");
+        let mut other = P8::default();
+        other.set_sfen(sfen).expect("failed to parse SFEN string");
+        other
+            .play_moves(&["b1_c3", "g8_f6", "g1_f3"])
+            .expect("illegal move");
+        assert_eq!(pos.hash(), other.hash());
+        let mut parsed = P8::default();
+        parsed
+            .set_sfen(&pos.generate_sfen())
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.hash(), parsed.hash());
+        pos.play_moves(&["f6_g8"]).expect("illegal move");
+        assert_ne!(pos.hash(), start);
+        for _ in 0..4 {
+            pos.unmake_move().expect("failed to unmake move");
+        }
+        assert_eq!(pos.hash(), start);
+        pos.update_side_to_move(Color::Black);
+        assert_ne!(pos.hash(), start);
+    }
+
+    #[test]
+    fn status() {
+        setup();
+        let cases = [
+            (
+                "K7/8/8/8/8/8/8/r6k w - 1",
+                Outcome::Check {
+                    color: Color::White,
+                },
+            ),
+            (
+                "K6r/PP6/8/8/8/8/8/7k w - 1",
+                Outcome::Checkmate {
+                    color: Color::Black,
+                },
+            ),
+            ("K7/8/1q6/8/8/8/8/7k w - 1", Outcome::Stalemate),
+            ("K7/8/8/8/8/8/8/7k w - 1", Outcome::MoveOk),
+        ];
+        for (sfen, outcome) in cases {
+            let mut pos = P8::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            let status = pos.status(Color::White);
+            assert_eq!(status, outcome, "{sfen}");
+            assert_eq!(
+                pos.in_check(Color::White),
+                matches!(
+                    status,
+                    Outcome::Check { .. } | Outcome::Checkmate { .. }
+                )
+            );
+            assert_eq!(
+                pos.is_checkmate(&Color::White),
+                matches!(status, Outcome::Checkmate { .. })
+            );
+            assert_eq!(
+                pos.is_stalemate(&Color::White).is_err()
+                    && !pos.in_check(Color::White),
+                status == Outcome::Stalemate
+            );
+        }
+    }
+
+    #[test]
+    fn starting_position() {
+        setup();
+        let pos = P8::starting_position(Variant::Standard)
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.perft(2), 400);
+        assert_eq!(pos.castling_rights(), [true; 4]);
+        let pos = P8::starting_position(Variant::StandardFairy)
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.variant(), Variant::StandardFairy);
+        assert_eq!(pos.piece_at(C1).unwrap().piece_type, PieceType::ArchBishop);
+    }
+
+    #[test]
+    fn legal_captures() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("K7/1R6/8/8/8/1r3n2/P7/1n5k w - 1")
+            .expect("failed to parse SFEN string");
+        let captures = pos.legal_captures(Color::White);
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures[&B2], BB8::from_squares(&[B6]));
+        assert_eq!(captures[&A7], BB8::from_squares(&[B8]));
+        for (from, targets) in pos.legal_moves(&Color::White) {
+            let enemy = targets & &pos.player_bb(Color::Black);
+            assert_eq!(
+                captures.get(&from).map_or(0, |bb| bb.len()),
+                enemy.len()
+            );
+        }
+    }
+
+    #[test]
+    fn play_detailed() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("K7/8/8/8/8/1r3n2/R7/7k w - 1")
+            .expect("failed to parse SFEN string");
+        let (outcome, move_data) =
+            pos.play_detailed("a7_a8").expect("illegal move");
+        assert_eq!(
+            outcome,
+            Outcome::Check {
+                color: Color::Black
+            }
+        );
+        assert!(move_data.is_check());
+        assert!(!move_data.is_checkmate());
+        assert!(move_data.captured_piece().is_none());
+        assert_eq!(
+            move_data.moved_piece().unwrap().piece_type,
+            PieceType::Rook
+        );
+        let (_, move_data) = pos.play_detailed("f6_g8").expect("illegal move");
+        assert!(!move_data.is_check());
+        let (_, move_data) = pos.play_detailed("a8_g8").expect("illegal move");
+        assert_eq!(
+            move_data.captured_piece().unwrap().piece_type,
+            PieceType::Knight
+        );
+        assert_eq!(pos.play_detailed("a1-a2"), Err(SfenError::IllegalMove));
+    }
+
+    #[test]
+    fn king_can_not_be_captured() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("K7/8/8/8/8/8/8/R6k w - 1")
+            .expect("failed to parse SFEN string");
+        assert!((pos.legal_moves_from(&A8) & &H8).is_empty());
+        assert!(pos.make_move(Move::new(A8, H8)).is_err());
+        assert_eq!(pos.find_king(&Color::Black), Some(H8));
+    }
+
+    #[test]
+    fn attacked_by() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3k3/8/8/8/8/8/8/K7 b - 1")
+            .expect("failed to parse SFEN string");
+        let attacks = pos.attacked_by(Color::White);
+        assert!((attacks & &E1).is_any());
+        assert!((attacks & &F1).is_empty());
+        assert!((pos.enemy_moves(&Color::Black) & &F1).is_any());
+        assert!((attacks & &B8).is_any());
+        assert_eq!(
+            pos.attack_maps()[Color::White.index()].len(),
+            attacks.len()
+        );
+    }
+
+    #[test]
+    fn mirror_and_flip() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/PP6/8/8/8/8/6pp/4k2r w Nb 3 Kk")
+            .expect("failed to parse SFEN string");
+        let mirrored = pos.mirror_files();
+        assert_eq!(
+            mirrored.generate_sfen(),
+            "3K3R/6PP/8/8/8/8/pp6/r2k4 w bN 3"
+        );
+        assert_eq!(mirrored.mirror_files().piece_at(A1), pos.piece_at(A1));
+        let flipped = pos.flip_colors();
+        assert_eq!(
+            flipped.generate_sfen(),
+            "4K2R/6PP/8/8/8/8/pp6/r3k3 b nB 3 Kk"
+        );
+        assert_eq!(flipped.flip_colors().generate_sfen(), pos.generate_sfen());
+        let mut parsed = P8::default();
+        parsed
+            .set_sfen(&flipped.generate_sfen())
+            .expect("failed to parse SFEN string");
+        assert_eq!(parsed.hash(), flipped.hash());
+    }
+
+    #[test]
+    fn timed_moves() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/8/8/8/8/8/8/7k w - 1")
+            .expect("failed to parse SFEN string");
+        assert_eq!(pos.time_left(Color::White), None);
+        pos.set_clock(Clock {
+            white_ms: 1000,
+            black_ms: 500,
+            increment_ms: 100,
+        });
+        pos.make_timed_move(Move::new(A1, A7), 400)
+            .expect("illegal move");
+        assert_eq!(pos.time_left(Color::White), Some(700));
+        assert!(pos.make_timed_move(Move::new(H8, A1), 100).is_err());
+        assert_eq!(pos.time_left(Color::Black), Some(500));
+        assert_eq!(
+            pos.make_timed_move(Move::new(H8, G8), 500),
+            Ok(Outcome::LostOnTime {
+                color: Color::Black
+            })
+        );
+        assert_eq!(pos.time_left(Color::Black), Some(0));
+        assert_eq!(
+            pos.make_timed_move(Move::new(H8, G8), 0),
+            Err(MoveError::GameEnded)
+        );
+    }
+
+    #[test]
+    fn discovered_check_candidates() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/8/8/N3B3/8/2P5/1p6/k7 w - 1")
+            .expect("failed to parse SFEN string");
+        let candidates: Vec<_> = pos
+            .discovered_check_candidates(Color::White)
+            .into_iter()
+            .collect();
+        assert_eq!(candidates, vec![A4]);
+        assert!(pos.discovered_check_candidates(Color::Black).is_empty());
+        pos.play("a4", "c5").expect("illegal move");
+        assert_eq!(
+            pos.outcome(),
+            &Outcome::Check {
+                color: Color::Black
+            }
+        );
+    }
+
+    #[test]
+    fn legal_moves_sorted() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbqkbnr w - 1")
+            .expect("failed to parse SFEN string");
+        let sorted = pos.legal_moves_sorted(Color::White);
+        let squares: Vec<_> = sorted.iter().map(|(sq, _)| *sq).collect();
+        assert_eq!(squares.len(), 16);
+        assert_eq!(squares[0], A1);
+        assert!(squares.windows(2).all(|w| w[0].index() < w[1].index()));
+        let map = pos.legal_moves(&Color::White);
+        for (sq, targets) in sorted {
+            assert_eq!(targets.len(), map[&sq].len());
+        }
+    }
+
+    #[test]
+    fn see() {
+        setup();
+        let cases = [
+            ("R3K3/R7/8/8/8/8/p7/r3k3 w - 1", A7, 10),
+            ("R3K3/8/8/8/8/8/p7/r3k3 w - 1", A7, -60),
+            ("K7/8/8/8/3b4/8/8/4k3 w - 1", D5, 0),
+            ("K2Q4/8/8/8/3n4/2p5/8/4k3 w - 1", D5, -70),
+        ];
+        for (sfen, to, value) in cases {
+            let mut pos = P8::default();
+            pos.set_sfen(sfen).expect("failed to parse SFEN string");
+            assert_eq!(pos.see(to, Color::White), value, "{sfen}");
+        }
+        let mut pos = P8::default();
+        pos.set_sfen("R3K3/R7/8/8/8/8/p7/r3k3 w - 1")
+            .expect("failed to parse SFEN string");
+        let blockers = pos.occupied_bb();
+        let attackers: Vec<_> = pos
+            .attackers_to(A7, Color::White, blockers)
+            .into_iter()
+            .collect();
+        assert_eq!(attackers, vec![A2]);
+    }
+
+    #[test]
+    fn piece_count() {
+        setup();
+        let mut pos = P8::default();
+        pos.set_sfen("RNBQKBNR/PPPPPPPP/8/8/8/8/pppp4/4k3 w - 1")
+            .expect("failed to parse SFEN string");
+        let pawn = |color| Piece {
+            piece_type: PieceType::Pawn,
+            color,
+        };
+        assert_eq!(pos.piece_count(pawn(Color::White)), 8);
+        assert_eq!(pos.piece_count(pawn(Color::Black)), 4);
+        assert_eq!(pos.total_pieces(Color::White), 16);
+        assert_eq!(pos.total_pieces(Color::Black), 5);
+    }
+
+    #[test]
+    fn file_and_rank() {
+        setup();
+        let pos = P8::default();
+        let file = pos.file(0).expect("file a exists");
+        assert_eq!(file.len(), 8);
+        assert!((file & &A1).is_any() && (file & &A8).is_any());
+        let rank = pos.rank(7).expect("rank 8 exists");
+        assert_eq!(rank.len(), 8);
+        assert!((rank & &H8).is_any());
+        assert_eq!(
+            pos.file(3).map(|bb| bb.into_iter().collect::<Vec<_>>()),
+            Some(crate::shuuro8::FILE_BB[3].into_iter().collect())
+        );
+        assert!(pos.file(8).is_none());
+        assert!(pos.rank(8).is_none());
+    }
+
+    #[test]
+    fn fairy_placement() {
+        setup();
+        let sfen = "3K4/8/L07/8/8/3c4/8/4k3 w GCg 5";
+        let mut pos = P8::default();
+        assert_eq!(pos.set_sfen(sfen), Err(SfenError::InvalidHand));
+        pos.update_variant(Variant::StandardFairy);
+        pos.set_sfen(sfen).expect("failed to parse SFEN string");
+        let squares = |pos: &P8<Square8, BB8<Square8>>, c| {
+            let p = Piece::from_sfen(c).unwrap();
+            let mut squares =
+                pos.placement_targets(p).into_iter().collect_vec();
+            squares.sort_by_key(|sq| sq.index());
+            squares
+        };
+        assert_eq!(squares(&pos, 'G'), [D2, D3]);
+        assert_eq!(squares(&pos, 'C'), [D2, D3]);
+        pos.place(Piece::from_sfen('G').unwrap(), D3).unwrap();
+        assert_eq!(squares(&pos, 'g'), [A8, B8, C8, D8, F8, G8, H8]);
+        pos.place(Piece::from_sfen('g').unwrap(), A8).unwrap();
+        assert_eq!(squares(&pos, 'C'), [A1, B1, C1, E1, F1, G1, H1]);
+    }
+
+    #[test]
+    fn from_squares() {
+        let mut bb = BB8::from_squares(&[A1, C3, C3, H8]);
+        assert_eq!(bb.len(), 3);
+        assert_eq!(bb.squares(), [H8, C3, A1]);
+        bb.set_squares(&[B2, H8]);
+        assert_eq!(bb, BB8::from_squares(&[A1, B2, C3, H8]));
+        assert_eq!(BB8::from_squares(&[]), BB8::empty());
+    }
+
+    #[test]
+    fn generate_plinths_seeded() {
+        setup();
+        let mut pos = P8::default();
+        pos.generate_plinths_seeded(42);
+        assert_eq!(pos.plinths(), vec![B4, E4, B6, H6]);
+    }
+}
@@ -1,11 +1,13 @@
 use std::{cmp::Ordering, fmt::Display};
 
-use crate::{Color, Square};
+use crate::{Color, SfenError, Square};
 
 const ASCII_1: u8 = 1;
 const ASCII_LOWER_A: u8 = b'a';
 const ASCII_LOWER_H: u8 = b'h';
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
 #[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Hash)]
 pub struct Square8 {
     inner: u8,
@@ -45,6 +47,20 @@ impl Iterator for SquareIter {
     }
 }
 
+impl TryFrom<u8> for Square8 {
+    type Error = SfenError;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Self::from_index(index).ok_or(SfenError::IllegalPieceFound)
+    }
+}
+
+impl From<Square8> for u8 {
+    fn from(sq: Square8) -> u8 {
+        sq.inner
+    }
+}
+
 impl Display for Square8 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", (self.file() + ASCII_LOWER_A) as char, {
@@ -227,6 +243,20 @@ mod tests {
         }
 
         assert!(Square8::from_index(145).is_none());
+        assert!(Square8::from_index(64).is_none());
+        assert_eq!(Square8::all().count(), 64);
+        assert!(Square8::all().enumerate().all(|(i, sq)| sq.index() == i));
+    }
+
+    #[test]
+    fn offset() {
+        use consts::*;
+        assert_eq!(G1.offset(-1, 2), Some(F3));
+        assert_eq!(E4.offset(2, -1), Some(G3));
+        assert_eq!(H1.offset(1, 2), None);
+        assert_eq!(A8.offset(-1, -2), None);
+        assert_eq!(B2.offset(-2, 1), None);
+        assert_eq!(D5.offset(0, 0), Some(D5));
     }
 
     #[test]
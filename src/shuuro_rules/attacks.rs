@@ -1,4 +1,6 @@
-use crate::shuuro_rules::{bitboard::BitBoard, Color, PieceType, Square};
+use crate::shuuro_rules::{
+    bitboard::BitBoard, zobrist, Color, PieceType, Square,
+};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Ray {
@@ -40,6 +42,32 @@ where
 
     fn get_giraffe_attacks(square: &S) -> B;
 
+    /// Returns giraffe attacks, same as leaper unless `lame` is set.
+    /// Lame giraffe steps one square diagonally and then three squares
+    /// straight, and any piece on that path blocks it.
+    fn get_giraffe_attacks_with(square: &S, blockers: B, lame: bool) -> B {
+        let attacks = Self::get_giraffe_attacks(square);
+        if !lame {
+            return attacks;
+        }
+        let (file, rank) = (square.file() as i8, square.rank() as i8);
+        let mut unblocked = B::empty();
+        for target in attacks {
+            let df = target.file() as i8 - file;
+            let dr = target.rank() as i8 - rank;
+            let blocked = (1..4).any(|i| {
+                let f = file + df.signum() * if df.abs() == 4 { i } else { 1 };
+                let r = rank + dr.signum() * if dr.abs() == 4 { i } else { 1 };
+                S::new(f as u8, r as u8)
+                    .is_some_and(|sq| (blockers & &sq).is_any())
+            });
+            if !blocked {
+                unblocked |= &target;
+            }
+        }
+        unblocked
+    }
+
     fn get_sliding_attacks(piece_type: PieceType, square: &S, blockers: B)
         -> B;
 
@@ -70,7 +98,8 @@ where
 
     fn get_pawn_moves(square: usize, color: Color) -> B;
 
-    fn init() {
+    /// Populates attack tables. Prefer `init`, which runs this only once.
+    fn init_tables() {
         Self::init_pawn_moves();
 
         Self::init_north_ray();
@@ -81,8 +110,18 @@ where
         Self::init_north_west_ray();
         Self::init_south_east_ray();
         Self::init_south_west_ray();
+        Self::init_quick();
         Self::init_between();
+        zobrist::init();
+    }
+
+    /// Initializes attack tables.
+    fn init() {
+        Self::init_tables();
     }
 
     fn between(sq1: S, sq2: S) -> B;
+
+    /// Returns all squares from `sq` to the edge of board in direction.
+    fn ray(dir: Ray, sq: &S) -> B;
 }
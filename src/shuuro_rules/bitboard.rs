@@ -15,6 +15,7 @@ pub trait BitBoard<S: Square>:
     + Not
     + Default
     + Display
+    + Eq
     + for<'a> BitAndAssign<&'a Self>
     + for<'a> BitOrAssign<&'a Self>
     + for<'a> BitXor<&'a Self, Output = Self>
@@ -33,9 +34,26 @@ pub trait BitBoard<S: Square>:
     fn is_empty(&self) -> bool;
     fn clear_at(&mut self, sq: S);
     fn clear_all(&mut self);
+    /// Number of set squares. Unlike `Iterator::count`, it doesn't consume.
     fn len(&self) -> u32;
+    /// Returns all set squares, without consuming bitboard.
+    fn squares(&self) -> Vec<S> {
+        (*self).collect()
+    }
     fn set_all(&mut self);
     fn pop(&mut self) -> Option<S>;
     fn pop_reverse(&mut self) -> Option<S>;
     fn from_square(sq: &S) -> Self;
+    /// Creates bitboard with all given squares set.
+    fn from_squares(squares: &[S]) -> Self {
+        let mut bb = Self::empty();
+        bb.set_squares(squares);
+        bb
+    }
+    /// Sets all given squares. Use `set_all` for whole board.
+    fn set_squares(&mut self, squares: &[S]) {
+        for sq in squares {
+            *self |= sq;
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use std::iter;
+use std::{fmt, iter, str::FromStr};
+
+use crate::shuuro_rules::SfenError;
 
 ///  Represents each side of player. Black player moves first.
 ///
@@ -14,7 +16,8 @@ use std::iter;
 ///    Color::NoColor => unreachable!()
 /// }
 /// ```
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 pub enum Color {
     Black = 0,
     White = 1,
@@ -66,12 +69,12 @@ impl Color {
     }
 }
 
-impl ToString for Color {
-    fn to_string(&self) -> String {
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Color::White => String::from("w"),
-            Color::Black => String::from("b"),
-            Color::NoColor => String::from(""),
+            Color::White => write!(f, "w"),
+            Color::Black => write!(f, "b"),
+            Color::NoColor => write!(f, ""),
         }
     }
 }
@@ -86,6 +89,19 @@ impl From<usize> for Color {
     }
 }
 
+impl FromStr for Color {
+    type Err = SfenError;
+
+    /// Parses `b`, `w`, `black` or `white`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "b" | "black" => Ok(Color::Black),
+            "w" | "white" => Ok(Color::White),
+            _ => Err(SfenError::IllegalSideToMove),
+        }
+    }
+}
+
 pub struct ColorIter {
     current: Option<Color>,
 }
@@ -132,4 +148,11 @@ mod tests {
         assert_eq!(Color::White.index(), 1);
         assert_eq!(Color::NoColor.index(), 2);
     }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("w".parse::<Color>(), Ok(Color::White));
+        assert_eq!("Black".parse::<Color>(), Ok(Color::Black));
+        assert_eq!("l".parse::<Color>(), Err(SfenError::IllegalSideToMove));
+    }
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use thiserror::Error;
 
 /// The error type for SFEN serialize/deserialize operations.
@@ -6,6 +8,9 @@ pub enum SfenError {
     #[error("data fields are missing")]
     MissingDataFields,
 
+    #[error("too many data fields")]
+    TooManyDataFields,
+
     #[error("an illegal piece notation is found")]
     IllegalPieceType,
 
@@ -29,6 +34,18 @@ pub enum SfenError {
 
     #[error("plinths can contain only knights")]
     IllegalPieceTypeOnPlynth,
+
+    #[error("an illegal castling rights notation is found")]
+    IllegalCastlingRights,
+
+    #[error("selection exceeds shop credit or piece limits")]
+    IllegalSelection,
+
+    #[error("hand exceeds shop credit or piece limits")]
+    InvalidHand,
+
+    #[error("unknown variant name")]
+    UnknownVariant,
 }
 
 /// Represents an error occurred during making a move.
@@ -55,6 +72,75 @@ pub enum MoveError {
     #[error("the move is inconsistent with the current position: {0}")]
     Inconsistent(&'static str),
 
+    #[error("illegal move from {from} to {to}: {reason}")]
+    IllegalMove {
+        from: String,
+        to: String,
+        reason: &'static str,
+    },
+
     #[error("repetition detected")]
     RepetitionDraw,
+
+    #[error("fifty moves without capture or pawn move")]
+    FiftyMoves,
+
+    #[error("game has ended")]
+    GameEnded,
+
+    #[error("pieces can be moved only during fight")]
+    NotInFight,
+
+    #[error("pieces can be placed only during deployment")]
+    NotInDeployment,
+}
+
+impl MoveError {
+    /// Creates `IllegalMove` error for move between two squares.
+    pub fn illegal<S: fmt::Display>(
+        from: S,
+        to: S,
+        reason: &'static str,
+    ) -> Self {
+        Self::IllegalMove {
+            from: from.to_string(),
+            to: to.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Represents an error occurred during placing a piece.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    #[error("not your turn")]
+    EnemysTurn,
+
+    #[error("the piece is not in hand")]
+    NotInHand,
+
+    #[error("the king must be placed first")]
+    KingNotPlaced,
+
+    #[error("the piece can not be placed on this square")]
+    IllegalSquare,
+
+    #[error("too many plinths for this board")]
+    TooManyPlinths,
+}
+
+/// Represents an error occurred during parsing a move.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MoveParseError {
+    #[error("square {0} does not exist")]
+    BadSquare(String),
+
+    #[error("squares must be separated with '_'")]
+    MissingSeparator,
+
+    #[error("suffix {0} is not a promotion piece")]
+    BadPromotion(String),
+
+    #[error("unknown piece {0}")]
+    UnknownPiece(String),
 }
@@ -16,7 +16,7 @@ use crate::shuuro_rules::{Color, Piece, PieceType};
 /// assert_eq!(3, hand.get(blue_pawn));
 /// assert_eq!(0, hand.get(red_pawn));
 /// ```
-
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Default)]
 pub struct Hand {
     pub inner: [u8; 18],
@@ -93,6 +93,41 @@ impl Hand {
         sum
     }
 
+    /// Returns all pieces of given color with their count.
+    pub fn pieces(&self, c: Color) -> Vec<(PieceType, u8)> {
+        PieceType::iter()
+            .filter_map(|piece_type| {
+                let count = self.get(Piece {
+                    piece_type,
+                    color: c,
+                });
+                (count > 0).then_some((piece_type, count))
+            })
+            .collect()
+    }
+
+    /// Returns shop value of all pieces of given color.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shuuro::{Color, Hand, PieceType};
+    ///
+    /// let hand = Hand::from("KQ2Pg");
+    /// assert_eq!(130, hand.value(Color::White));
+    /// assert_eq!(70, hand.value(Color::Black));
+    /// assert_eq!(
+    ///     vec![(PieceType::King, 1), (PieceType::Queen, 1), (PieceType::Pawn, 2)],
+    ///     hand.pieces(Color::White)
+    /// );
+    /// ```
+    pub fn value(&self, c: Color) -> u16 {
+        self.pieces(c)
+            .iter()
+            .map(|(pt, count)| pt.price() as u16 * *count as u16)
+            .sum()
+    }
+
     /// Set hand with all pieces from str.
     pub fn set_hand(&mut self, s: &str) {
         let hand = Hand::from(s);
@@ -12,7 +12,9 @@ pub mod shop;
 pub mod square;
 pub mod subvariant;
 pub mod variant;
+pub mod zobrist;
 
+pub use self::attacks::Ray;
 pub use self::color::{Color, ColorIter};
 pub use self::piece::Piece;
 pub use self::piece_type::PieceType;
@@ -1,11 +1,15 @@
 use crate::{
+    attacks::Attacks,
+    bitboard::BitBoard,
     color::Color,
-    shuuro_rules::{Piece, Square},
+    position::Play,
+    shuuro_rules::{MoveParseError, Piece, PieceType, Square},
 };
-use std::fmt;
+use std::{fmt, hash::Hash};
 
 /// Represents a move which either is a normal move or a drop move.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum Move<S: Square> {
     Buy {
         piece: Piece,
@@ -19,9 +23,17 @@ pub enum Move<S: Square> {
         from: S,
         to: S,
         placed: Piece,
+        promotion: Option<PieceType>,
         move_data: MoveData,
         fen: String,
     },
+    Castle {
+        king_from: S,
+        king_to: S,
+        rook_from: S,
+        rook_to: S,
+        move_data: MoveData,
+    },
 }
 
 impl<S: Square> Move<S> {
@@ -34,14 +46,40 @@ impl<S: Square> Move<S> {
                 piece_type: crate::PieceType::Rook,
                 color: Color::Black,
             },
+            promotion: None,
+            move_data: MoveData::default(),
+            fen: String::new(),
+        }
+    }
+
+    /// Creating new normal move where a pawn promotes to `piece_type`.
+    ///
+    /// Moves created with `new` promote pawns to queen.
+    pub fn new_promotion(from: S, to: S, piece_type: PieceType) -> Self {
+        Self::Normal {
+            from,
+            to,
+            placed: Piece {
+                piece_type: crate::PieceType::Rook,
+                color: Color::Black,
+            },
+            promotion: Some(piece_type),
             move_data: MoveData::default(),
             fen: String::new(),
         }
     }
 
+    /// Returns piece type chosen for promotion, if any.
+    pub fn promotion(&self) -> Option<PieceType> {
+        match self {
+            Self::Normal { promotion, .. } => *promotion,
+            _ => None,
+        }
+    }
+
     /// Creates a new instance of `Self` from SFEN formatted string.
     pub fn from_sfen(s: &str) -> Option<Self> {
-        if s.len() > 7 {
+        if s.len() > 8 {
             return None;
         }
 
@@ -95,7 +133,7 @@ impl<S: Square> Move<S> {
         None
     }
 
-    /// Getting normal move from str.
+    /// Getting normal move from str, with optional promotion suffix.
     pub fn get_normal_move(s: &str) -> Option<Self> {
         let mut fen_parts = s.split('_');
         if let Some(from) = fen_parts.next() {
@@ -104,6 +142,9 @@ impl<S: Square> Move<S> {
                     if let Some(to) = Square::from_sfen(to) {
                         return Some(Self::new(from, to));
                     }
+                    if let Some((to, piece_type)) = Self::promotion_suffix(to) {
+                        return Some(Self::new_promotion(from, to, piece_type));
+                    }
                 }
             }
         }
@@ -111,18 +152,38 @@ impl<S: Square> Move<S> {
         None
     }
 
+    /// Splits target square with promotion suffix, like `a8n`.
+    fn promotion_suffix(s: &str) -> Option<(S, PieceType)> {
+        let (i, ch) = s.char_indices().last()?;
+        let to = S::from_sfen(&s[..i])?;
+        match PieceType::from_char(ch)? {
+            PieceType::King | PieceType::Pawn => None,
+            piece_type => Some((to, piece_type)),
+        }
+    }
+
     pub fn to_fen(&self) -> String {
         match &self {
             Move::Put { fen, .. } => String::from(fen),
             Move::Normal { fen, .. } => String::from(fen),
-            Move::Buy { .. } => self.to_string(),
+            Move::Buy { .. } | Move::Castle { .. } => self.to_string(),
         }
     }
 
     pub fn format(&self) -> String {
+        if let Move::Castle {
+            king_from, king_to, ..
+        } = &self
+        {
+            if king_to.file() > king_from.file() {
+                return String::from("O-O");
+            }
+            return String::from("O-O-O");
+        }
         if let Move::Normal {
             from,
             to,
+            promotion,
             move_data,
             ..
         } = &self
@@ -146,12 +207,11 @@ impl<S: Square> Move<S> {
                     piece
                 }
             };
-            let promote = {
-                if move_data.promoted && piece.is_empty() {
-                    "=Q"
-                } else {
-                    ""
+            let promote = match promotion {
+                Some(piece_type) if piece.is_empty() => {
+                    format!("={}", piece_type.to_string().to_uppercase())
                 }
+                _ => String::new(),
             };
 
             let same = {
@@ -203,6 +263,21 @@ impl<S: Square> Move<S> {
     }
 }
 
+impl<S: Square + Hash> Move<S> {
+    /// Returns this move in algebraic notation, as played in `pos`.
+    ///
+    /// Returns `None` if move is not legal in `pos`.
+    pub fn to_san<B, A, P>(&self, pos: &P) -> Option<String>
+    where
+        B: BitBoard<S>,
+        A: Attacks<S, B>,
+        P: Play<S, B, A>,
+    {
+        let position = pos.probe(self.clone()).ok()?;
+        position.move_history().last().map(|m| m.format())
+    }
+}
+
 impl<S: Square> TryFrom<String> for Move<S> {
     type Error = ();
 
@@ -230,6 +305,52 @@ impl<S: Square> TryFrom<String> for Move<S> {
     }
 }
 
+impl<S: Square> TryFrom<&str> for Move<S> {
+    type Error = MoveParseError;
+
+    /// Parses normal (`a1_a2`, `a7_a8n`), put (`Q@a1`) and buy (`+Q`) moves.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let square = |s: &str| {
+            S::from_sfen(s).ok_or(MoveParseError::BadSquare(String::from(s)))
+        };
+        let piece = |s: &str| {
+            let mut chars = s.chars();
+            match (chars.next().and_then(Piece::from_sfen), chars.next()) {
+                (Some(p), None) if p.piece_type != PieceType::Plinth => Ok(p),
+                _ => Err(MoveParseError::UnknownPiece(String::from(s))),
+            }
+        };
+        if let Some(p) = value.strip_prefix('+') {
+            Ok(Self::Buy { piece: piece(p)? })
+        } else if let Some((p, to)) = value.split_once('@') {
+            Ok(Self::Put {
+                piece: piece(p)?,
+                to: square(to)?,
+                fen: String::new(),
+            })
+        } else if let Some((from, to)) = value.split_once('_') {
+            let from = square(from)?;
+            if let Some(to) = S::from_sfen(to) {
+                return Ok(Self::new(from, to));
+            }
+            if let Some((to, piece_type)) = Self::promotion_suffix(to) {
+                return Ok(Self::new_promotion(from, to, piece_type));
+            }
+            match to.char_indices().last() {
+                Some((i, ch))
+                    if ch.is_alphabetic()
+                        && S::from_sfen(&to[..i]).is_some() =>
+                {
+                    Err(MoveParseError::BadPromotion(String::from(&to[i..])))
+                }
+                _ => Err(MoveParseError::BadSquare(String::from(to))),
+            }
+        } else {
+            Err(MoveParseError::MissingSeparator)
+        }
+    }
+}
+
 impl<S: Square> fmt::Display for Move<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
@@ -239,25 +360,29 @@ impl<S: Square> fmt::Display for Move<S> {
             Move::Put { to, piece, .. } => {
                 write!(f, "{piece}@{to}")
             }
+            Move::Castle {
+                king_from, king_to, ..
+            } => {
+                write!(f, "{king_from}_{king_to}")
+            }
             Move::Normal {
                 from,
                 to,
-                move_data,
+                promotion,
                 ..
             } => {
-                write!(
-                    f,
-                    "{}_{}{}",
-                    from,
-                    to,
-                    if move_data.promoted { "fixthis" } else { "" }
-                )
+                write!(f, "{from}_{to}")?;
+                match promotion.and_then(PieceType::to_char) {
+                    Some(ch) => write!(f, "{ch}"),
+                    None => Ok(()),
+                }
             }
         }
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct MoveData {
     check: bool,
     checkmate: bool,
@@ -266,6 +391,8 @@ pub struct MoveData {
     captured: Option<Piece>,
     piece: Option<Piece>,
     promoted: bool,
+    castling_rights: [bool; 4],
+    halfmove_clock: u16,
 }
 
 impl MoveData {
@@ -295,4 +422,62 @@ impl MoveData {
         self.piece = piece;
         self
     }
+
+    /// Saves castling rights and halfmove clock from before the move.
+    pub fn previous(
+        mut self,
+        castling_rights: [bool; 4],
+        halfmove_clock: u16,
+    ) -> Self {
+        self.castling_rights = castling_rights;
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    /// Returns piece that was captured with this move.
+    pub fn captured_piece(&self) -> Option<Piece> {
+        self.captured
+    }
+
+    /// Returns piece that was moved, before promotion.
+    pub fn moved_piece(&self) -> Option<Piece> {
+        self.piece
+    }
+
+    /// Returns castling rights from before the move.
+    pub fn previous_castling_rights(&self) -> [bool; 4] {
+        self.castling_rights
+    }
+
+    /// Returns halfmove clock from before the move.
+    pub fn previous_halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    /// Check if moved piece was promoted.
+    pub fn is_promoted(&self) -> bool {
+        self.promoted
+    }
+
+    /// Check if move gives check.
+    pub fn is_check(&self) -> bool {
+        self.check
+    }
+
+    /// Check if move gives checkmate.
+    pub fn is_checkmate(&self) -> bool {
+        self.checkmate
+    }
+
+    /// Check if another piece of same type could reach the same square from
+    /// the same file, so notation needs rank of origin.
+    pub fn is_same_file(&self) -> bool {
+        self.same_file
+    }
+
+    /// Check if another piece of same type could reach the same square from
+    /// the same rank, so notation needs file of origin.
+    pub fn is_same_rank(&self) -> bool {
+        self.same_rank
+    }
 }
@@ -1,8 +1,9 @@
-use crate::shuuro_rules::{Color, PieceType};
-use std::fmt;
+use crate::shuuro_rules::{Color, PieceType, SfenError};
+use std::{fmt, str::FromStr};
 
 /// Represents a piece on the game board.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
@@ -93,6 +94,19 @@ impl fmt::Display for Piece {
         }
     }
 }
+impl FromStr for Piece {
+    type Err = SfenError;
+
+    /// Parses piece from single SFEN character.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Piece::from_sfen), chars.next()) {
+            (Some(piece), None) => Ok(piece),
+            _ => Err(SfenError::IllegalPieceType),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,4 +270,13 @@ mod tests {
         assert_eq!(Color::Black, rpc.flip().color);
         assert_eq!(Color::White, bpc.flip().color);
     }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("Q".parse::<Piece>(), Ok(Piece::from_sfen('Q').unwrap()));
+        assert_eq!("L".parse::<Piece>(), Ok(Piece::from_sfen('L').unwrap()));
+        for case in ["", "QQ", "j", "10"] {
+            assert_eq!(case.parse::<Piece>(), Err(SfenError::IllegalPieceType));
+        }
+    }
 }
@@ -1,7 +1,8 @@
 use std::{fmt, iter};
 
 /// Represents a kind of pieces.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum PieceType {
     King = 0,
     Queen = 1,
@@ -23,21 +24,40 @@ impl PieceType {
 
     /// Creates a new instance of `PieceType` from SFEN formatted string.
     pub fn from_sfen(c: char) -> Option<PieceType> {
-        Some(match c {
-            'k' | 'K' => PieceType::King,
-            'q' | 'Q' => PieceType::Queen,
-            'r' | 'R' => PieceType::Rook,
-            'b' | 'B' => PieceType::Bishop,
-            'n' | 'N' => PieceType::Knight,
-            'p' | 'P' => PieceType::Pawn,
-            'c' | 'C' => PieceType::Chancellor,
-            'a' | 'A' => PieceType::ArchBishop,
-            'g' | 'G' => PieceType::Giraffe,
-            'L' => PieceType::Plinth,
+        match c {
+            'L' => Some(PieceType::Plinth),
+            c => PieceType::from_char(c),
+        }
+    }
+
+    /// Creates piece type from hand or shop letter, ignoring case.
+    ///
+    /// Letters `k`, `q`, `r`, `b`, `n` and `p` are used in every variant.
+    /// Letters `c` (Chancellor), `a` (ArchBishop) and `g` (Giraffe) are used
+    /// only in fairy variants. Plinth is not a hand piece and has no letter.
+    pub fn from_char(c: char) -> Option<PieceType> {
+        Some(match c.to_ascii_lowercase() {
+            'k' => PieceType::King,
+            'q' => PieceType::Queen,
+            'r' => PieceType::Rook,
+            'b' => PieceType::Bishop,
+            'n' => PieceType::Knight,
+            'p' => PieceType::Pawn,
+            'c' => PieceType::Chancellor,
+            'a' => PieceType::ArchBishop,
+            'g' => PieceType::Giraffe,
             _ => return None,
         })
     }
 
+    /// Returns lowercase letter of this piece type. Inverse of `from_char`.
+    pub fn to_char(self) -> Option<char> {
+        match self {
+            PieceType::Plinth => None,
+            pt => pt.to_string().chars().next(),
+        }
+    }
+
     /// Returns an instance of `PieceType` after promotion.
     ///
     /// # Examples
@@ -75,6 +95,35 @@ impl PieceType {
         }
     }
 
+    /// Returns price of this piece type in shop.
+    pub fn price(self) -> i32 {
+        use self::PieceType::*;
+
+        match self {
+            Queen => 110,
+            Rook | Giraffe => 70,
+            Bishop | Knight => 40,
+            Pawn => 10,
+            Chancellor | ArchBishop => 130,
+            King | Plinth => 0,
+        }
+    }
+
+    /// Returns maximum number of pieces of this type that can be bought.
+    pub fn max_count(self) -> u8 {
+        use self::PieceType::*;
+
+        match self {
+            King => 1,
+            Queen | Chancellor | ArchBishop => 3,
+            Rook => 6,
+            Bishop | Knight => 9,
+            Pawn => 18,
+            Giraffe => 4,
+            Plinth => 0,
+        }
+    }
+
     /// Checks if this piece type can be a part of hand pieces.
     pub fn is_hand_piece(self) -> bool {
         matches!(
@@ -205,6 +254,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_char() {
+        for pt in PieceType::iter() {
+            match pt.to_char() {
+                Some(c) => {
+                    assert_eq!(Some(pt), PieceType::from_char(c));
+                    assert_eq!(
+                        Some(pt),
+                        PieceType::from_char(c.to_ascii_uppercase())
+                    );
+                }
+                None => assert_eq!(pt, PieceType::Plinth),
+            }
+        }
+        assert!(PieceType::from_char('l').is_none());
+        assert!(PieceType::from_char('L').is_none());
+    }
+
     #[test]
     fn to_sfen() {
         let ok_cases = [
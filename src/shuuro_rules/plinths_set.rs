@@ -1,24 +1,59 @@
-use rand::prelude::*;
-
 use crate::{bitboard::BitBoard, Square};
 
 type Section = (u8, u8, u8, u8, u8);
 
+/// Small xorshift64* generator for plinths. Unlike `rand` generators its
+/// output never changes, so stored seed always gives same plinths.
+pub struct PlinthRng {
+    state: u64,
+}
+
+impl PlinthRng {
+    pub fn new(seed: u64) -> Self {
+        // Zero state would only produce zeros.
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Self {
+            state: if state == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                state
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns number in `low..high`.
+    pub fn gen_range(&mut self, low: u8, high: u8) -> u8 {
+        low + ((self.next_u64() >> 32) % u64::from(high - low)) as u8
+    }
+}
+
 pub trait PlinthGen<S: Square, B: BitBoard<S>> {
     fn king_moves(&self, sq: S) -> B;
 
     fn y(&self) -> u8;
 
-    fn generate_plinths(&self, sections: &[Section]) -> B {
+    fn generate_plinths(
+        &self,
+        sections: &[Section],
+        rang: &mut PlinthRng,
+    ) -> B {
         let mut plinths = B::empty();
-        let mut rang = thread_rng();
         let rank = self.y();
         for section in sections {
             let mut bb = B::empty();
             let mut i = 0;
             while i < section.4 {
-                let y = rang.gen_range(section.0..section.1);
-                let x = rang.gen_range(section.2..section.3);
+                let y = rang.gen_range(section.0, section.1);
+                let x = rang.gen_range(section.2, section.3);
                 let sq = (y * rank) + x;
                 if let Some(sq) = S::from_index(sq) {
                     if (plinths & &sq).is_empty() && (bb & &sq).is_empty() {
@@ -1,13 +1,16 @@
 use std::{
-    clone::Clone, cmp::Ordering, collections::HashMap, hash::Hash,
+    clone::Clone, cmp::Ordering, collections::HashMap, fmt, hash::Hash,
     marker::PhantomData,
 };
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
 
 use crate::{
-    attacks::Attacks, bitboard::BitBoard, Color, Hand, Move, MoveData,
-    MoveError, Piece, PieceType, SfenError, Square, Variant,
+    attacks::{Attacks, Ray},
+    bitboard::BitBoard,
+    zobrist, Color, Hand, Move, MoveData, MoveError, Piece, PieceType,
+    PlacementError, SfenError, Square, SubVariant, Variant,
 };
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -52,7 +55,58 @@ where
     }
 }
 
+/// Serialized form of a position: SFEN plus data that SFEN doesn't carry.
+#[cfg(all(feature = "serde", any(feature = "shuuro12", feature = "shuuro8")))]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct PositionData<S: Square> {
+    pub sfen: String,
+    pub variant: Variant,
+    pub outcome: Outcome,
+    pub move_history: Vec<Move<S>>,
+    pub halfmove_clock: u16,
+    pub clock: Option<Clock>,
+}
+
+#[cfg(all(feature = "serde", any(feature = "shuuro12", feature = "shuuro8")))]
+impl<S: Square + Hash> PositionData<S> {
+    pub fn new<B, A, P>(position: &P) -> Self
+    where
+        B: BitBoard<S>,
+        A: Attacks<S, B>,
+        P: Position<S, B, A>,
+    {
+        Self {
+            sfen: position.generate_sfen(),
+            variant: position.variant(),
+            outcome: position.outcome().clone(),
+            move_history: position.move_history().to_vec(),
+            halfmove_clock: position.halfmove_clock(),
+            clock: position.clock(),
+        }
+    }
+
+    /// Rebuilds position from SFEN and restores move history.
+    pub fn restore<B, A, P>(self) -> Result<P, SfenError>
+    where
+        B: BitBoard<S>,
+        A: Attacks<S, B>,
+        P: Position<S, B, A>,
+    {
+        let mut position = P::new();
+        position.update_variant(self.variant);
+        position.set_sfen(&self.sfen)?;
+        position.set_move_history(self.move_history);
+        position.update_outcome(self.outcome);
+        position.update_halfmove_clock(self.halfmove_clock);
+        if let Some(clock) = self.clock {
+            position.set_clock(clock);
+        }
+        Ok(position)
+    }
+}
+
 /// Outcome stores information about outcome after move.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Outcome {
     Check { color: Color },
@@ -61,25 +115,31 @@ pub enum Outcome {
     Nothing,
     DrawByRepetition,
     DrawByMaterial,
+    DrawByFiftyMoves,
     Stalemate,
+    Resign { color: Color },
+    DrawByAgreement,
+    LostOnTime { color: Color },
     MoveNotOk,
     MoveOk,
 }
 
-impl ToString for Outcome {
-    fn to_string(&self) -> String {
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            Outcome::Check { color } => format!("Check_{}", color.to_string()),
-            Outcome::Checkmate { color } => {
-                format!("Checkmate_{}", color.to_string())
-            }
-            Outcome::Draw => "Draw".to_string(),
-            Outcome::Nothing => "Live".to_string(),
-            Outcome::DrawByRepetition => "RepetitionDraw".to_string(),
-            Outcome::DrawByMaterial => "MaterialDraw".to_string(),
-            Outcome::Stalemate => "Stalemate".to_string(),
-            Outcome::MoveOk => "Live".to_string(),
-            Outcome::MoveNotOk => "Illegal move".to_string(),
+            Outcome::Check { color } => write!(f, "Check_{color}"),
+            Outcome::Checkmate { color } => write!(f, "Checkmate_{color}"),
+            Outcome::Draw => write!(f, "Draw"),
+            Outcome::Nothing => write!(f, "Live"),
+            Outcome::DrawByRepetition => write!(f, "RepetitionDraw"),
+            Outcome::DrawByMaterial => write!(f, "MaterialDraw"),
+            Outcome::DrawByFiftyMoves => write!(f, "FiftyMovesDraw"),
+            Outcome::Stalemate => write!(f, "Stalemate"),
+            Outcome::Resign { color } => write!(f, "Resign_{color}"),
+            Outcome::DrawByAgreement => write!(f, "AgreedDraw"),
+            Outcome::LostOnTime { color } => write!(f, "LostOnTime_{color}"),
+            Outcome::MoveOk => write!(f, "Live"),
+            Outcome::MoveNotOk => write!(f, "Illegal move"),
         }
     }
 }
@@ -97,10 +157,144 @@ impl Into<i32> for Outcome {
             Outcome::DrawByRepetition => 4,
             Outcome::Draw => 5,
             Outcome::DrawByMaterial => 6,
+            Outcome::DrawByFiftyMoves => 7,
+            Outcome::Resign { color: _ } => 8,
+            Outcome::DrawByAgreement => 9,
+            Outcome::LostOnTime { color: _ } => 10,
         }
     }
 }
 
+impl Outcome {
+    /// Check if game is over.
+    pub fn is_terminal(&self) -> bool {
+        !matches!(
+            self,
+            Outcome::Check { .. }
+                | Outcome::Nothing
+                | Outcome::MoveOk
+                | Outcome::MoveNotOk
+        )
+    }
+
+    /// Returns color of the player who won the game.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { color } => Some(*color),
+            Outcome::Resign { color } | Outcome::LostOnTime { color } => {
+                Some(color.flip())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Phase of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStage {
+    /// Players are buying pieces.
+    Selection,
+    /// Players are placing pieces from hand.
+    Deployment,
+    /// Pieces are moving on board.
+    Fight,
+}
+
+/// Remaining time of both players in milliseconds.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Clock {
+    pub white_ms: u64,
+    pub black_ms: u64,
+    /// Time added after each move.
+    pub increment_ms: u64,
+}
+
+impl Clock {
+    /// Returns remaining time of color.
+    pub fn time_left(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white_ms,
+            _ => self.black_ms,
+        }
+    }
+
+    fn update(&mut self, color: Color, ms: u64) {
+        match color {
+            Color::White => self.white_ms = ms,
+            _ => self.black_ms = ms,
+        }
+    }
+}
+
+/// Differences between two boards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardDiff<S: Square> {
+    /// Squares with old and new piece, ordered by square index.
+    pub pieces: Vec<(S, Option<Piece>, Option<Piece>)>,
+    /// Squares where plinth was added or removed.
+    pub plinths: Vec<S>,
+}
+
+/// Fields of SFEN string, parsed without creating a position.
+#[derive(Debug, Clone)]
+pub struct SfenFields {
+    pub board: String,
+    pub side_to_move: Color,
+    pub hand: Hand,
+    pub ply: u16,
+    pub castling_rights: [bool; 4],
+    pub halfmove_clock: u16,
+}
+
+/// Parses SFEN fields. Board field is not checked.
+pub fn parse_sfen_fields(s: &str) -> Result<SfenFields, SfenError> {
+    let mut parts = s.split_whitespace();
+    let mut next = || parts.next().ok_or(SfenError::MissingDataFields);
+    let board = String::from(next()?);
+    let side_to_move = parse_stm(next()?)?;
+    let hand = match next()? {
+        "-" => Hand::default(),
+        hand => Hand::from(hand),
+    };
+    let ply = next()?.parse()?;
+    let castling_rights = parse_castling(next().unwrap_or("-"))?;
+    let halfmove_clock = next().unwrap_or("0").parse()?;
+    Ok(SfenFields {
+        board,
+        side_to_move,
+        hand,
+        ply,
+        castling_rights,
+        halfmove_clock,
+    })
+}
+
+fn parse_stm(s: &str) -> Result<Color, SfenError> {
+    match s {
+        "b" => Ok(Color::Black),
+        "w" => Ok(Color::White),
+        _ => Err(SfenError::IllegalSideToMove),
+    }
+}
+
+fn parse_castling(s: &str) -> Result<[bool; 4], SfenError> {
+    let mut rights = [false; 4];
+    if s != "-" {
+        for ch in s.chars() {
+            let index = match ch {
+                'K' => 0,
+                'Q' => 1,
+                'k' => 2,
+                'q' => 3,
+                _ => return Err(SfenError::IllegalCastlingRights),
+            };
+            rights[index] = true;
+        }
+    }
+    Ok(rights)
+}
+
 pub trait Position<S, B, A>
 where
     S: Square + Hash,
@@ -114,6 +308,125 @@ where
         + Play<S, B, A>
         + Rules<S, B, A>,
 {
+    /// Returns current stage of the game.
+    fn stage(&self) -> GameStage {
+        self.game_stage()
+    }
+
+    /// Returns starting position for variant. Standard variants start with
+    /// pieces on back ranks, others with empty board for selection.
+    fn starting_position(variant: Variant) -> Result<Self, SfenError> {
+        let sfen = match variant {
+            Variant::Standard => {
+                format!("{} KQkq", SubVariant::Standard.starting_position())
+            }
+            Variant::StandardFairy => format!(
+                "{} KQkq",
+                SubVariant::StandardFairy1.starting_position()
+            ),
+            _ => format!("{} - 1", Self::empty_placement_board()),
+        };
+        let mut pos = Self::new();
+        pos.update_variant(variant);
+        pos.set_sfen(&sfen)?;
+        Ok(pos)
+    }
+
+    /// Returns copy of position with every square moved by `f`. Pieces on
+    /// plinths stay on them. If `flip` is true, colors of pieces, hands,
+    /// castling rights and side to move are swapped. Move history is cleared.
+    fn transform(&self, f: impl Fn(S) -> S, flip: bool) -> Self {
+        let mut pos = self.clone();
+        pos.set_move_history(Vec::new());
+        for sq in S::all() {
+            pos.set_piece(sq, None);
+        }
+        pos.empty_all_bb();
+        let plinth = Piece {
+            piece_type: PieceType::Plinth,
+            color: Color::NoColor,
+        };
+        for sq in self.player_bb(Color::NoColor) {
+            pos.update_player(plinth, &f(sq));
+            pos.set_piece(f(sq), None);
+        }
+        for sq in self.player_bb(Color::Black) | &self.player_bb(Color::White) {
+            if let Some(mut piece) = *self.piece_at(sq) {
+                if flip {
+                    piece.color = piece.color.flip();
+                }
+                pos.update_player(piece, &f(sq));
+            }
+        }
+        if flip {
+            let hand = self.get_hand(Color::White, false)
+                + &self.get_hand(Color::Black, false);
+            let hand: String = hand
+                .chars()
+                .map(|ch| match ch.is_uppercase() {
+                    true => ch.to_ascii_lowercase(),
+                    false => ch.to_ascii_uppercase(),
+                })
+                .collect();
+            pos.set_hand(&hand);
+            pos.update_side_to_move(self.side_to_move().flip());
+            let rights = self.castling_rights();
+            pos.update_castling_rights([
+                rights[2], rights[3], rights[0], rights[1],
+            ]);
+        }
+        pos
+    }
+
+    /// Returns copy of position with files reversed. Castling rights are
+    /// removed, because king and rooks leave their files.
+    fn mirror_files(&self) -> Self {
+        let last = self.dimensions() - 1;
+        let mut pos = self.transform(
+            |sq| S::new(last - sq.file(), sq.rank()).unwrap_or(sq),
+            false,
+        );
+        pos.update_castling_rights([false; 4]);
+        pos
+    }
+
+    /// Returns copy of position with ranks reversed and colors swapped.
+    fn flip_colors(&self) -> Self {
+        let last = self.dimensions() - 1;
+        self.transform(
+            |sq| S::new(sq.file(), last - sq.rank()).unwrap_or(sq),
+            true,
+        )
+    }
+
+    /// Returns random legal move or placement for side to move.
+    fn random_move(&self, rng: &mut impl RngCore) -> Option<Move<S>> {
+        if self.outcome().is_terminal() {
+            return None;
+        }
+        let c = self.side_to_move();
+        let mut moves = Vec::new();
+        match self.stage() {
+            GameStage::Selection => return None,
+            GameStage::Deployment => {
+                for piece_type in PieceType::iter() {
+                    let piece = Piece {
+                        piece_type,
+                        color: c,
+                    };
+                    for to in self.placement_targets(piece) {
+                        moves.push(Move::Put {
+                            to,
+                            piece,
+                            fen: String::new(),
+                        });
+                    }
+                }
+            }
+            GameStage::Fight => moves = self.all_moves(c),
+        }
+        moves.choose(rng).cloned()
+    }
 }
 
 pub trait Board<S, B, A>
@@ -153,6 +466,8 @@ where
     fn ply(&self) -> u16;
     /// Increment ply
     fn increment_ply(&mut self);
+    /// Decrement ply
+    fn decrement_ply(&mut self);
     /// Change side to move.
     fn flip_side_to_move(&mut self);
     /// Set new stm
@@ -164,11 +479,55 @@ where
     /// Returns current variant.
     fn variant(&self) -> Variant;
     /// Changing to other variant.
+    ///
+    /// Hand is not validated again. Call this before `set_sfen` when hand
+    /// has pieces which only this variant allows.
     fn update_variant(&mut self, variant: Variant);
+    /// Returns if threefold repetition ends the game.
+    fn auto_draw_repetition(&self) -> bool;
+    /// Returns if insufficient material ends the game.
+    fn auto_draw_material(&self) -> bool;
+    /// Enable or disable draw by threefold repetition.
+    fn update_auto_draw_repetition(&mut self, enabled: bool);
+    /// Enable or disable draw by insufficient material.
+    fn update_auto_draw_material(&mut self, enabled: bool);
+    /// Returns clock of timed game.
+    fn clock(&self) -> Option<Clock>;
+    /// Set clock for timed game.
+    fn set_clock(&mut self, clock: Clock);
+    /// Returns remaining time of color, `None` if game is not timed.
+    fn time_left(&self, color: Color) -> Option<u64> {
+        self.clock().map(|clock| clock.time_left(color))
+    }
+    /// Returns zobrist key of pieces on board.
+    fn zobrist(&self) -> u64;
+    /// Returns zobrist hash of pieces, side to move and castling rights.
+    fn hash(&self) -> u64 {
+        self.zobrist()
+            ^ zobrist::side_key(self.side_to_move())
+            ^ zobrist::castling_key(self.castling_rights())
+    }
+    /// Returns if played moves are saved in move history.
+    fn record_history(&self) -> bool;
+    /// Returns copy of position without move history. Moves played on
+    /// the copy are not recorded, so they cannot be unmade.
+    fn clone_for_search(&self) -> Self;
+    /// Returns number of plies since last capture or pawn move.
+    fn halfmove_clock(&self) -> u16;
+    /// Update halfmove clock.
+    fn update_halfmove_clock(&mut self, clock: u16);
+    /// Returns castling rights in `KQkq` order. Only standard 8x8 castles.
+    fn castling_rights(&self) -> [bool; 4] {
+        [false; 4]
+    }
+    /// Update castling rights.
+    fn update_castling_rights(&mut self, _rights: [bool; 4]) {}
     /// Insert new sfen to sfen history.
     fn insert_sfen(&mut self, sfen: Move<S>);
     /// Insert new Move2 to move_history.
     fn insert_move(&mut self, m: Move<S>);
+    /// Remove last move from move_history.
+    fn pop_move(&mut self) -> Option<Move<S>>;
     /// Clear sfen_history
     fn clear_sfen_history(&mut self);
     /// Set sfen history.
@@ -194,6 +553,106 @@ where
         }
         v
     }
+    /// Returns number of placement and fighting plies from move history.
+    fn phase_ply_counts(&self) -> (u16, u16) {
+        let mut counts = (0, 0);
+        for m in self.move_history() {
+            match m {
+                Move::Put { .. } => counts.0 += 1,
+                Move::Normal { .. } | Move::Castle { .. } => counts.1 += 1,
+                Move::Buy { .. } => (),
+            }
+        }
+        counts
+    }
+    /// Returns all pieces on board, ordered by square index.
+    fn board_state(&self) -> Vec<(S, Piece)> {
+        let pieces = self.occupied_bb() & &!self.player_bb(Color::NoColor);
+        let mut state: Vec<(S, Piece)> = pieces
+            .filter_map(|sq| self.piece_at(sq).map(|p| (sq, p)))
+            .collect();
+        state.sort_by_key(|(sq, _)| sq.index());
+        state
+    }
+    /// Compares pieces and plinths on all squares with other board.
+    fn diff(&self, other: &Self) -> BoardDiff<S> {
+        let piece = |board: &Self, sq: S| {
+            board
+                .piece_at(sq)
+                .filter(|p| p.piece_type != PieceType::Plinth)
+        };
+        let dimensions = self.dimensions();
+        let pieces = S::all()
+            .filter(|sq| sq.file() < dimensions && sq.rank() < dimensions)
+            .filter_map(|sq| {
+                let (old, new) = (piece(self, sq), piece(other, sq));
+                (old != new).then_some((sq, old, new))
+            })
+            .collect();
+        let mut plinths: Vec<S> = (self.player_bb(Color::NoColor)
+            ^ &other.player_bb(Color::NoColor))
+            .collect();
+        plinths.sort_by_key(|sq| sq.index());
+        BoardDiff { pieces, plinths }
+    }
+    /// Returns all plinths, ordered by square index.
+    fn plinths(&self) -> Vec<S> {
+        let mut plinths: Vec<S> = self.player_bb(Color::NoColor).collect();
+        plinths.sort_by_key(|sq| sq.index());
+        plinths
+    }
+    /// Returns pieces in hand for both players, White first.
+    fn hands(&self) -> [(Color, Vec<(PieceType, u8)>); 2] {
+        [Color::White, Color::Black].map(|color| {
+            let pieces = PieceType::iter()
+                .filter_map(|piece_type| {
+                    let count = self.hand(Piece { piece_type, color });
+                    (count > 0).then_some((piece_type, count))
+                })
+                .collect();
+            (color, pieces)
+        })
+    }
+    /// Returns all pieces captured by `by` color, in order of capture.
+    fn captured(&self, by: Color) -> Vec<PieceType> {
+        let mut pieces = Vec::new();
+        for m in self.move_history() {
+            if let Move::Normal {
+                placed, move_data, ..
+            } = m
+            {
+                if placed.color != by {
+                    continue;
+                }
+                if let Some(captured) = move_data.captured_piece() {
+                    pieces.push(captured.piece_type);
+                }
+            }
+        }
+        pieces
+    }
+    /// Returns zobrist key of board, plinths, side to move, castling rights,
+    /// hands and variant. Ply and move history are ignored.
+    ///
+    /// Keys come from fixed seed, so key is same across runs and Rust
+    /// releases and can be stored.
+    fn position_key(&self) -> u64 {
+        let plinth = Piece {
+            piece_type: PieceType::Plinth,
+            color: Color::NoColor,
+        };
+        let mut key = self.hash() ^ zobrist::variant_key(self.variant());
+        for sq in self.player_bb(Color::NoColor) {
+            key ^= zobrist::piece_key(plinth, sq.index());
+        }
+        for color in [Color::Black, Color::White] {
+            for piece_type in PieceType::iter() {
+                let piece = Piece { piece_type, color };
+                key ^= zobrist::hand_key(piece, self.hand(piece));
+            }
+        }
+        key
+    }
     /// Get hand count for Piece.
     fn hand(&self, p: Piece) -> u8;
     /// Get hand in form of String
@@ -204,6 +663,12 @@ where
     fn decrement_hand(&mut self, p: Piece);
     /// Dimensions of board.
     fn dimensions(&self) -> u8;
+
+    /// Returns bitboard of file, or `None` if file is out of board.
+    fn file(&self, f: u8) -> Option<B>;
+
+    /// Returns bitboard of rank, or `None` if rank is out of board.
+    fn rank(&self, r: u8) -> Option<B>;
     /// Returns `Square` if King is available.
     fn find_king(&self, c: &Color) -> Option<S> {
         let mut bb = self.type_bb(&PieceType::King) & &self.player_bb(*c);
@@ -213,6 +678,14 @@ where
             None
         }
     }
+    /// Returns number of given pieces on board.
+    fn piece_count(&self, p: Piece) -> u32 {
+        (self.type_bb(&p.piece_type) & &self.player_bb(p.color)).len()
+    }
+    /// Returns number of all pieces of color on board.
+    fn total_pieces(&self, color: Color) -> u32 {
+        self.player_bb(color).len()
+    }
 }
 
 pub trait Sfen<S, B, A>
@@ -232,7 +705,7 @@ where
             return self.generate_sfen();
         }
         if move_history.is_empty() {
-            format!("{} {}", sfen_history.first().unwrap(), ply);
+            return format!("{} {}", sfen_history.first().unwrap(), ply);
         }
         format!(
             "{} {}",
@@ -287,6 +760,37 @@ where
             "w"
         };
 
+        let mut sfen =
+            format!("{} {} {} {}", fen, color, self.hand_sfen(), self.ply());
+        let clock = self.halfmove_clock();
+        if clock > 0 || self.castling_rights().iter().any(|r| *r) {
+            sfen.push(' ');
+            sfen.push_str(&self.castling_sfen());
+        }
+        if clock > 0 {
+            sfen.push_str(&format!(" {clock}"));
+        }
+        sfen
+    }
+
+    /// Returns castling rights field of sfen.
+    fn castling_sfen(&self) -> String {
+        let rights = self.castling_rights();
+        let s: String = ['K', 'Q', 'k', 'q']
+            .iter()
+            .zip(rights.iter())
+            .filter(|(_, r)| **r)
+            .map(|(c, _)| *c)
+            .collect();
+        if s.is_empty() {
+            String::from("-")
+        } else {
+            s
+        }
+    }
+
+    /// Returns hand field of sfen for both colors.
+    fn hand_sfen(&self) -> String {
         let black = self.get_hand(Color::Black, false);
         let white = self.get_hand(Color::White, false);
         let mut hand = String::new();
@@ -295,7 +799,7 @@ where
         if hand.is_empty() {
             hand = "-".to_string();
         }
-        format!("{} {} {} {}", fen, color, hand, self.ply())
+        hand
     }
 
     fn add_space(&self, n: u8, mut s: String) -> String {
@@ -320,18 +824,36 @@ where
     fn update_player(&mut self, piece: Piece, sq: &S);
 
     fn parse_sfen_stm(&mut self, s: &str) -> Result<(), SfenError> {
-        let stm = match s {
-            "b" => Color::Black,
-            "w" => Color::White,
-            _ => return Err(SfenError::IllegalSideToMove),
-        };
-        self.update_side_to_move(stm);
+        self.update_side_to_move(parse_stm(s)?);
         Ok(())
     }
 
+    /// Parses board part of SFEN.
+    /// Plinths can also be written as `_` before a piece and `.` when empty.
+    /// `generate_sfen` still writes them as `L` and `L0`.
     fn parse_sfen_board(&mut self, fen: &str) -> Result<(), SfenError> {
+        let mut legacy = String::with_capacity(fen.len());
+        let mut chars = fen.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '.' => legacy.push_str("L0"),
+                '_' => {
+                    match chars.peek().and_then(|ch| Piece::from_sfen(*ch)) {
+                        Some(p) if p.piece_type != PieceType::Plinth => {
+                            legacy.push('L')
+                        }
+                        _ => return Err(SfenError::IllegalBoardState),
+                    }
+                }
+                ch => legacy.push(ch),
+            }
+        }
+        let fen = legacy;
         let ranks = fen.split('/');
         let dimension = self.dimensions();
+        if ranks.clone().count() != dimension as usize {
+            return Err(SfenError::IllegalBoardState);
+        }
         self.empty_all_bb();
         for (rank, file) in ranks.enumerate() {
             if rank >= dimension as usize {
@@ -398,17 +920,46 @@ where
         Ok(())
     }
 
+    /// Parse hand part of SFEN.
+    ///
+    /// # Errors
+    ///
+    /// Hand is checked against current variant. Fairy pieces are rejected
+    /// with `SfenError::InvalidHand` unless `update_variant` was called with
+    /// fairy variant first.
     fn parse_sfen_hand(&mut self, s: &str) -> Result<(), SfenError> {
         if s == "-" {
             self.clear_hand();
             return Ok(());
         }
 
-        self.new_hand(Hand::from(s));
+        let is_piece = |ch: char| {
+            Piece::from_sfen(ch)
+                .is_some_and(|p| p.piece_type != PieceType::Plinth)
+        };
+        if !s.chars().all(|ch| ch.is_ascii_digit() || is_piece(ch)) {
+            return Err(SfenError::IllegalPieceType);
+        }
+        let hand = Hand::from(s);
+        let variant = self.variant();
+        for color in [Color::White, Color::Black] {
+            let over_limit = hand.pieces(color).iter().any(|(pt, count)| {
+                !variant.can_buy(pt) || *count > variant.max_count(pt)
+            });
+            if over_limit || hand.value(color) as i32 > variant.start_credit() {
+                return Err(SfenError::InvalidHand);
+            }
+        }
+        self.new_hand(hand);
 
         Ok(())
     }
 
+    fn parse_sfen_castling(&mut self, s: &str) -> Result<(), SfenError> {
+        self.update_castling_rights(parse_castling(s)?);
+        Ok(())
+    }
+
     /// Saves position in sfen_history.
     fn log_position(&mut self) {
         let mut sfen = self.generate_sfen().split(' ').take(3).join(" ");
@@ -436,6 +987,67 @@ where
         }
         true
     }
+
+    /// Returns `Deployment` while any hand has pieces, `Selection` when
+    /// board has no kings and `Fight` otherwise.
+    fn game_stage(&self) -> GameStage {
+        if [Color::White, Color::Black]
+            .iter()
+            .any(|c| !self.is_hand_empty(*c, PieceType::Plinth))
+        {
+            GameStage::Deployment
+        } else if self.type_bb(&PieceType::King).is_empty() {
+            GameStage::Selection
+        } else {
+            GameStage::Fight
+        }
+    }
+
+    /// Check that parsed board and hand could come from a real game.
+    ///
+    /// Pieces in hand are bought, so `parse_sfen_hand` caps them with
+    /// `Variant::max_count`. Pieces on board may go over the same caps,
+    /// because pawns can promote to them, but all pieces over the caps
+    /// together can not outnumber missing pawns.
+    fn validate_board(&self) -> Result<(), SfenError> {
+        let variant = self.variant();
+        let placement = self.game_stage() == GameStage::Deployment;
+        for color in [Color::White, Color::Black] {
+            let player = self.player_bb(color);
+            let count = |piece_type: PieceType| {
+                let piece = Piece { piece_type, color };
+                self.piece_count(piece) as u8 + self.hand(piece)
+            };
+            let kings = self.piece_count(Piece {
+                piece_type: PieceType::King,
+                color,
+            });
+            if kings > 1 || (!placement && kings == 0 && player.is_any()) {
+                return Err(SfenError::IllegalBoardState);
+            }
+            let pawns = self.type_bb(&PieceType::Pawn) & &player;
+            if pawns.into_iter().any(|sq| sq.in_promotion_zone(color)) {
+                return Err(SfenError::IllegalBoardState);
+            }
+            let pawn_cap = variant.max_count(&PieceType::Pawn);
+            let missing_pawns = pawn_cap - count(PieceType::Pawn).min(pawn_cap);
+            let mut promoted = 0;
+            for pt in PieceType::iter() {
+                if matches!(pt, PieceType::King | PieceType::Plinth) {
+                    continue;
+                }
+                let over = count(pt).saturating_sub(variant.max_count(&pt));
+                if !variant.can_promote_to(&pt) && over > 0 {
+                    return Err(SfenError::IllegalBoardState);
+                }
+                promoted += over;
+            }
+            if promoted > missing_pawns {
+                return Err(SfenError::IllegalBoardState);
+            }
+        }
+        Ok(())
+    }
 }
 
 pub trait Placement<S, B, A>
@@ -443,12 +1055,83 @@ where
     S: Square + Hash,
     B: BitBoard<S>,
     A: Attacks<S, B>,
-    Self: Sized + Clone + Board<S, B, A> + Sfen<S, B, A>,
+    Self: Sized + Clone + Board<S, B, A> + Sfen<S, B, A> + Rules<S, B, A>,
 {
     // PLACEMENT PART
 
     /// Generate random plinths.
-    fn generate_plinths(&mut self);
+    fn generate_plinths(&mut self) {
+        self.generate_plinths_seeded(rand::thread_rng().gen());
+    }
+
+    /// Generate plinths from seed. Same seed gives same plinths.
+    fn generate_plinths_seeded(&mut self, seed: u64);
+
+    /// Sections of board where plinths are generated, each with number of
+    /// plinths in it.
+    fn plinth_sections(&self) -> Vec<(B, u32)>;
+
+    /// BitBoard with all squares where plinths can be generated.
+    fn plinth_area(&self) -> B {
+        let mut area = B::empty();
+        for (section, _) in self.plinth_sections() {
+            area |= &section;
+        }
+        area
+    }
+
+    /// Number of plinths generated for this board.
+    fn plinth_count(&self) -> u32 {
+        self.plinth_sections().iter().map(|(_, count)| count).sum()
+    }
+
+    /// Replace plinths with ones on given squares.
+    ///
+    /// Every square must be empty and no section from `plinth_sections` can
+    /// have more plinths than generator would put there.
+    fn set_plinths(&mut self, squares: &[S]) -> Result<(), PlacementError> {
+        if squares.len() > self.plinth_count() as usize {
+            return Err(PlacementError::TooManyPlinths);
+        }
+        let area = self.plinth_area();
+        let mut plinths = B::empty();
+        for sq in squares {
+            if (area & sq).is_empty() || self.piece_at(*sq).is_some() {
+                return Err(PlacementError::IllegalSquare);
+            }
+            plinths |= sq;
+        }
+        if self
+            .plinth_sections()
+            .iter()
+            .any(|(section, count)| (plinths & section).len() > *count)
+        {
+            return Err(PlacementError::TooManyPlinths);
+        }
+        self.clear_plinths();
+        let plinth = Piece {
+            piece_type: PieceType::Plinth,
+            color: Color::NoColor,
+        };
+        for sq in plinths {
+            self.update_player(plinth, &sq);
+            self.set_piece(sq, None);
+        }
+        Ok(())
+    }
+
+    /// Remove all plinths from board.
+    fn clear_plinths(&mut self) {
+        for sq in self.player_bb(Color::NoColor) {
+            self.xor_player_bb(Color::NoColor, sq);
+            if (self.type_bb(&PieceType::Plinth) & &sq).is_any() {
+                self.xor_type_bb(PieceType::Plinth, sq);
+                if self.piece_at(sq).is_none() {
+                    self.xor_occupied(sq);
+                }
+            }
+        }
+    }
 
     /// BitBoard with all available squares for white.
     fn white_placement_attacked_ranks(&self) -> B;
@@ -493,6 +1176,46 @@ where
         }
     }
 
+    /// Returns BitBoard with squares where king can be dropped right now.
+    fn king_drop_squares(&self, c: Color) -> B {
+        if c == Color::NoColor {
+            return B::empty();
+        }
+        let attacked = self.attack_maps()[c.flip().index()];
+        self.king_squares::<6>(&c) & &!self.player_bb(c) & &!attacked
+    }
+
+    /// Returns squares that piece would attack if placed on square.
+    fn drop_attacks(&self, p: Piece, sq: S) -> B {
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        match p.piece_type {
+            PieceType::Pawn => self.get_moves(&sq, &p, !B::empty()),
+            _ => self.get_moves(&sq, &p, blockers | &sq),
+        }
+    }
+
+    /// Check if White's and Black's deployed pieces are vertical mirrors.
+    fn is_mirror_deployment(&self) -> bool {
+        let last = self.dimensions() - 1;
+        let white = self.player_bb(Color::White);
+        if white.len() != self.player_bb(Color::Black).len() {
+            return false;
+        }
+        for sq in white {
+            let mirrored = match S::new(sq.file(), last - sq.rank()) {
+                Some(sq) => sq,
+                None => return false,
+            };
+            match (self.piece_at(sq), self.piece_at(mirrored)) {
+                (Some(w), Some(b))
+                    if w.piece_type == b.piece_type
+                        && b.color == Color::Black => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
     fn can_pawn_move(&self, p: Piece) -> bool {
         self.is_hand_empty(p.color, PieceType::Pawn)
     }
@@ -540,8 +1263,10 @@ where
             }
             B::empty()
         };
-        let checks = self.checks(&p.color);
-        if checks.is_any() {
+        let (checks, checkers) = self.slider_checks(&p.color);
+        if checkers > 1 {
+            return B::empty();
+        } else if checks.is_any() {
             return checks;
         } else if !self.is_king_placed(p.color)
             && p.piece_type != PieceType::King
@@ -555,18 +1280,37 @@ where
         }
     }
 
+    /// Returns squares where check from sliding pieces can be blocked
+    /// during placement. Empty if there is no check or it is double check.
     fn checks(&self, attacked_color: &Color) -> B {
+        match self.slider_checks(attacked_color) {
+            (lines, 1) => lines,
+            _ => B::empty(),
+        }
+    }
+
+    /// Returns squares between king and checking sliders on placement ranks
+    /// and number of checking sliders.
+    fn slider_checks(&self, attacked_color: &Color) -> (B, u32) {
         let king =
             self.type_bb(&PieceType::King) & &self.player_bb(*attacked_color);
         if king.is_empty() {
-            return B::empty();
+            return (B::empty(), 0);
         }
+        let ranks = match *attacked_color {
+            Color::White => self.white_placement_attacked_ranks(),
+            Color::Black => self.black_placement_attacked_ranks(),
+            Color::NoColor => return (B::empty(), 0),
+        };
         let occupied_bb = self.occupied_bb() | &self.player_bb(Color::NoColor);
         let king_sq = (king | &B::empty()).pop().unwrap();
+        let mut lines = B::empty();
+        let mut checkers = 0;
 
         for pt in [PieceType::Rook, PieceType::Bishop] {
             let king_attacks =
                 A::get_sliding_attacks(pt, &king_sq, occupied_bb);
+            let mut them = B::empty();
 
             for p in [
                 PieceType::Queen,
@@ -584,76 +1328,151 @@ where
                 if pt == PieceType::Bishop && !p.is_bishop_type() {
                     continue;
                 }
+                them |= &(self.type_bb(&p)
+                    & &self.player_bb(attacked_color.flip()));
+            }
 
-                let them =
-                    self.type_bb(&p) & &self.player_bb(attacked_color.flip());
-
-                if (them & &king_attacks).is_any() {
-                    match *attacked_color {
-                        Color::White => {
-                            let ranks = self.white_placement_attacked_ranks();
-                            let attacks = (ranks & &king_attacks) & &!king;
-                            return attacks;
-                        }
-                        Color::Black => {
-                            let ranks = self.black_placement_attacked_ranks();
-                            let attacks = (ranks & &king_attacks) & &!king;
-                            return attacks;
-                        }
-                        Color::NoColor => {
-                            return B::empty();
-                        }
-                    }
-                }
+            for attacker in them & &king_attacks {
+                checkers += 1;
+                lines |= &(ranks & &A::between(king_sq, attacker));
             }
         }
-        B::empty()
+        (lines, checkers)
     }
 
     fn update_bb(&mut self, p: Piece, sq: S);
 
-    fn place(&mut self, p: Piece, sq: S) -> Option<String> {
-        if p.color != self.side_to_move() {
-            return None;
-        } else if self.hand(p) > 0 && (self.empty_squares(p) & &sq).is_any() {
-            self.update_bb(p, sq);
-            self.decrement_hand(p);
-            let move_record = Move::Put {
-                to: sq,
-                piece: p,
-                fen: String::new(),
+    /// Returns all possible placements for pieces in hand.
+    fn all_placements(&self, c: Color) -> Vec<Move<S>> {
+        let mut moves = Vec::new();
+        for piece_type in PieceType::iter() {
+            let piece = Piece {
+                piece_type,
+                color: c,
             };
-            let sfen =
-                self.generate_sfen().split(' ').next().unwrap().to_string();
-            let hand = {
-                let s = self.get_hand(Color::White, false)
-                    + &self.get_hand(Color::Black, false);
-                if s.is_empty() {
-                    String::from(" ")
-                } else {
-                    s
-                }
-            };
-            self.increment_ply();
-            let ply = self.ply();
-
-            self.insert_move(move_record.clone());
-            if !self.is_hand_empty(p.color.flip(), PieceType::Plinth) {
-                self.update_side_to_move(p.color.flip());
-            }
-            let record = format!(
-                "{}_{}_{}_{}_{}",
-                &move_record.to_string(),
-                &sfen,
-                hand,
-                self.side_to_move().to_string(),
-                ply
-            );
-            self.update_last_move(&record);
-            // self.insert_sfen(&record);
-            return Some(record);
+            if piece_type == PieceType::Plinth || self.hand(piece) == 0 {
+                continue;
+            }
+            for to in self.empty_squares(piece) {
+                moves.push(Move::Put {
+                    to,
+                    piece,
+                    fen: String::new(),
+                });
+            }
+        }
+        moves
+    }
+
+    /// Check if color has to place the king before any other piece.
+    fn must_place_king(&self, c: Color) -> bool {
+        !self.is_king_placed(c)
+    }
+
+    /// Returns all squares where piece from hand can be placed.
+    fn placement_targets(&self, p: Piece) -> B {
+        if p.color != self.side_to_move()
+            || self.hand(p) == 0
+            || (p.piece_type != PieceType::King
+                && self.must_place_king(p.color))
+        {
+            return B::empty();
+        }
+        self.empty_squares(p)
+    }
+
+    /// Check if piece from hand can be placed on square, without placing it.
+    fn can_place(&self, p: Piece, sq: S) -> bool {
+        (self.placement_targets(p) & &sq).is_any()
+    }
+
+    /// Place piece from hand, returning reason if placement is not possible.
+    fn place(&mut self, p: Piece, sq: S) -> Result<String, PlacementError> {
+        if p.color != self.side_to_move() {
+            return Err(PlacementError::EnemysTurn);
+        } else if self.hand(p) == 0 {
+            return Err(PlacementError::NotInHand);
+        } else if p.piece_type != PieceType::King
+            && self.must_place_king(p.color)
+        {
+            return Err(PlacementError::KingNotPlaced);
+        } else if (self.empty_squares(p) & &sq).is_empty() {
+            return Err(PlacementError::IllegalSquare);
+        }
+        self.update_bb(p, sq);
+        self.decrement_hand(p);
+        let move_record = Move::Put {
+            to: sq,
+            piece: p,
+            fen: String::new(),
+        };
+        let sfen = self.generate_sfen().split(' ').next().unwrap().to_string();
+        let hand = {
+            let s = self.get_hand(Color::White, false)
+                + &self.get_hand(Color::Black, false);
+            if s.is_empty() {
+                String::from(" ")
+            } else {
+                s
+            }
+        };
+        self.increment_ply();
+        let ply = self.ply();
+
+        self.insert_move(move_record.clone());
+        if !self.is_hand_empty(p.color.flip(), PieceType::Plinth) {
+            self.update_side_to_move(p.color.flip());
         }
-        None
+        let record = format!(
+            "{}_{}_{}_{}_{}",
+            &move_record.to_string(),
+            &sfen,
+            hand,
+            self.side_to_move(),
+            ply
+        );
+        self.update_last_move(&record);
+        // self.insert_sfen(&record);
+        Ok(record)
+    }
+
+    /// Same as `place`, but also returns placement squares for side to move
+    /// after the drop. Side to move is flipped only if opponent still has
+    /// pieces in hand, otherwise same side places again. Map is empty when
+    /// both hands are empty.
+    fn place_detailed(
+        &mut self,
+        p: Piece,
+        sq: S,
+    ) -> Result<(String, HashMap<PieceType, B>), PlacementError> {
+        let record = self.place(p, sq)?;
+        Ok((record, self.placement_squares()))
+    }
+
+    /// Returns squares where each piece from hand of side to move can be
+    /// placed.
+    fn placement_squares(&self) -> HashMap<PieceType, B> {
+        let color = self.side_to_move();
+        PieceType::iter()
+            .filter(|pt| *pt != PieceType::Plinth)
+            .filter_map(|piece_type| {
+                let targets =
+                    self.placement_targets(Piece { piece_type, color });
+                targets.is_any().then_some((piece_type, targets))
+            })
+            .collect()
+    }
+
+    /// Place pieces in order. Stops on first placement that fails and
+    /// returns its index together with piece and square.
+    fn place_many(
+        &mut self,
+        placements: &[(Piece, S)],
+    ) -> Result<(), (usize, Piece, S)> {
+        for (i, (p, sq)) in placements.iter().enumerate() {
+            self.place(*p, *sq).map_err(|_| (i, *p, *sq))?;
+        }
+        Ok(())
     }
 
     fn empty_placement_board() -> String;
@@ -682,146 +1501,841 @@ where
                 return Err(SfenError::IllegalPieceFound);
             }
         };
-        let m = Move::new(from, to);
-        let outcome = self.make_move(m);
-        match outcome {
-            Ok(i) => {
-                self.update_outcome(i);
+        let m = Move::new(from, to);
+        let outcome = self.make_move(m);
+        if self.settle_move(outcome).is_err() {
+            return Err(SfenError::IllegalMove);
+        }
+        Ok(self.outcome())
+    }
+
+    /// Make move that took `elapsed_ms` to think. Side whose time runs out
+    /// loses on time, otherwise increment is added after move is played.
+    fn make_timed_move(
+        &mut self,
+        m: Move<S>,
+        elapsed_ms: u64,
+    ) -> Result<Outcome, MoveError> {
+        let color = self.side_to_move();
+        let mut clock = match self.clock() {
+            Some(clock) => clock,
+            None => return self.make_move(m),
+        };
+        if self.is_game_ended() {
+            return Err(MoveError::GameEnded);
+        }
+        let left = clock.time_left(color);
+        if elapsed_ms >= left {
+            clock.update(color, 0);
+            self.set_clock(clock);
+            self.flag(color);
+            return Ok(self.outcome().clone());
+        }
+        let ply = self.ply();
+        let result = self.make_move(m);
+        if self.ply() != ply {
+            clock.update(color, left - elapsed_ms + clock.increment_ms);
+            self.set_clock(clock);
+        }
+        result
+    }
+
+    /// Play move in `a1_a2` format and return outcome with move data.
+    /// Move data is empty for castling or when history is not recorded.
+    fn play_detailed(
+        &mut self,
+        game_move: &str,
+    ) -> Result<(Outcome, MoveData), SfenError> {
+        let (from, to) =
+            game_move.split_once('_').ok_or(SfenError::IllegalMove)?;
+        let outcome = self.play(from, to)?.clone();
+        let move_data = match self.move_history().last() {
+            Some(Move::Normal { move_data, .. }) if self.record_history() => {
+                *move_data
+            }
+            _ => MoveData::default(),
+        };
+        Ok((outcome, move_data))
+    }
+
+    /// Play moves in `a1_a2` format. Stops on first move that fails and
+    /// returns its index together with error.
+    fn play_moves(
+        &mut self,
+        moves: &[&str],
+    ) -> Result<&Outcome, (usize, SfenError)> {
+        for (i, m) in moves.iter().enumerate() {
+            let (from, to) =
+                m.split_once('_').ok_or((i, SfenError::IllegalMove))?;
+            self.play(from, to).map_err(|error| (i, error))?;
+        }
+        Ok(self.outcome())
+    }
+
+    /// Update outcome from result of `make_move`. Draws are not errors here.
+    fn settle_move(
+        &mut self,
+        result: Result<Outcome, MoveError>,
+    ) -> Result<(), MoveError> {
+        match result {
+            Ok(i) => {
+                self.update_outcome(i);
+            }
+            Err(error) => match error {
+                MoveError::RepetitionDraw => {
+                    self.update_outcome(Outcome::DrawByRepetition)
+                }
+                MoveError::Draw => self.update_outcome(Outcome::Draw),
+                MoveError::DrawByInsufficientMaterial => {
+                    self.update_outcome(Outcome::DrawByMaterial)
+                }
+                MoveError::DrawByStalemate => {
+                    self.update_outcome(Outcome::Stalemate)
+                }
+                MoveError::FiftyMoves => {
+                    self.update_outcome(Outcome::DrawByFiftyMoves)
+                }
+                _ => {
+                    return Err(error);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Take back last move from move history. Castling rights and halfmove
+    /// clock are restored from the move record.
+    fn unmake_move(&mut self) -> Result<(), MoveError> {
+        let (from, to, placed, move_data) = match self.move_history().last() {
+            Some(Move::Normal {
+                from,
+                to,
+                placed,
+                move_data,
+                ..
+            }) => (*from, *to, *placed, *move_data),
+            Some(Move::Castle {
+                king_from,
+                king_to,
+                rook_from,
+                rook_to,
+                move_data,
+            }) => {
+                let (king_from, king_to) = (*king_from, *king_to);
+                let (rook_from, rook_to) = (*rook_from, *rook_to);
+                let move_data = *move_data;
+                let color = self.side_to_move().flip();
+                let king = Piece {
+                    piece_type: PieceType::King,
+                    color,
+                };
+                let rook = Piece {
+                    piece_type: PieceType::Rook,
+                    color,
+                };
+                self.move_piece(king_to, king_from, king);
+                self.move_piece(rook_to, rook_from, rook);
+                self.update_castling_rights(
+                    move_data.previous_castling_rights(),
+                );
+                self.update_halfmove_clock(move_data.previous_halfmove_clock());
+                self.update_side_to_move(color);
+                self.decrement_ply();
+                self.pop_move();
+                self.update_outcome(Outcome::MoveOk);
+                return Ok(());
+            }
+            Some(Move::Put { .. }) => {
+                return Err(MoveError::Inconsistent(
+                    "Placement cannot be taken back",
+                ))
+            }
+            _ => return Err(MoveError::Inconsistent("No move to unmake")),
+        };
+        let moved = move_data.moved_piece().unwrap_or(placed);
+        self.set_piece(to, None);
+        self.xor_player_bb(placed.color, to);
+        self.xor_type_bb(placed.piece_type, to);
+        self.xor_occupied(to);
+        if let Some(captured) = move_data.captured_piece() {
+            self.set_piece(to, Some(captured));
+            self.xor_player_bb(captured.color, to);
+            self.xor_type_bb(captured.piece_type, to);
+            self.xor_occupied(to);
+        }
+        self.set_piece(from, Some(moved));
+        self.xor_player_bb(moved.color, from);
+        self.xor_type_bb(moved.piece_type, from);
+        self.xor_occupied(from);
+        self.update_side_to_move(moved.color);
+        self.decrement_ply();
+        self.pop_move();
+        self.update_castling_rights(move_data.previous_castling_rights());
+        self.update_halfmove_clock(move_data.previous_halfmove_clock());
+        if self.in_check(moved.color) {
+            self.update_outcome(Outcome::Check { color: moved.color });
+        } else {
+            self.update_outcome(Outcome::MoveOk);
+        }
+        Ok(())
+    }
+
+    /// Returns (sfen, move played, final outcome) for each ply of the game.
+    /// Last record is current position without move.
+    ///
+    /// Returns error if move history contains placement, because placement
+    /// cannot be taken back with `unmake_move`.
+    #[allow(clippy::type_complexity)]
+    fn training_records(
+        &self,
+    ) -> Result<Vec<(String, Option<Move<S>>, Outcome)>, MoveError> {
+        let outcome = self.outcome().clone();
+        let mut position = self.clone();
+        let mut records = vec![(self.generate_sfen(), None, outcome.clone())];
+        while let Some(m) = position.move_history().last().cloned() {
+            position.unmake_move()?;
+            records.push((position.generate_sfen(), Some(m), outcome.clone()));
+        }
+        records.reverse();
+        Ok(records)
+    }
+
+    /// Player with `color` resigns.
+    fn resign(&mut self, color: Color) {
+        self.update_outcome(Outcome::Resign { color });
+    }
+
+    /// Both players agree to draw.
+    fn agree_draw(&mut self) {
+        self.update_outcome(Outcome::DrawByAgreement);
+    }
+
+    /// Player with `color` runs out of time.
+    fn flag(&mut self, color: Color) {
+        self.update_outcome(Outcome::LostOnTime { color });
+    }
+
+    /// Check if game was ended by resignation, agreement or time.
+    fn is_game_ended(&self) -> bool {
+        matches!(
+            self.outcome(),
+            Outcome::Resign { .. }
+                | Outcome::DrawByAgreement
+                | Outcome::LostOnTime { .. }
+        )
+    }
+
+    /// Exports game in PGN format, starting from first fighting move.
+    /// Placement moves are written in a comment block.
+    fn to_pgn(&self) -> String {
+        let mut start = self.clone();
+        while let Some(Move::Normal { .. } | Move::Castle { .. }) =
+            start.move_history().last()
+        {
+            if start.unmake_move().is_err() {
+                break;
+            }
+        }
+        let outcome = self.outcome();
+        let result = match outcome.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            _ if outcome.is_terminal() => "1/2-1/2",
+            _ => "*",
+        };
+        let mut pgn = format!(
+            "[Variant \"{}\"]\n[FEN \"{}\"]\n[Result \"{result}\"]\n\n",
+            self.variant(),
+            start.generate_sfen()
+        );
+        let placement: Vec<String> = start
+            .move_history()
+            .iter()
+            .filter(|m| matches!(m, Move::Put { .. }))
+            .map(|m| m.to_string())
+            .collect();
+        if !placement.is_empty() {
+            pgn.push_str(&format!("{{ {} }} ", placement.join(" ")));
+        }
+        let fighting = &self.move_history()[start.move_history().len()..];
+        let mut number = 1;
+        let mut color = start.side_to_move();
+        if color == Color::Black && !fighting.is_empty() {
+            pgn.push_str("1... ");
+        }
+        for m in fighting {
+            if color == Color::White {
+                pgn.push_str(&format!("{number}. "));
+            } else {
+                number += 1;
+            }
+            pgn.push_str(&m.format());
+            pgn.push(' ');
+            color = color.flip();
+        }
+        pgn.push_str(result);
+        pgn
+    }
+
+    /// Returns copy of position after move is played.
+    fn probe(&self, m: Move<S>) -> Result<Self, MoveError> {
+        let mut position = self.clone();
+        let result = position.make_move(m);
+        position.settle_move(result)?;
+        Ok(position)
+    }
+
+    /// Returns position after move is played, without move history.
+    /// Intended for search, where history and notation are not needed.
+    fn try_move(&self, m: Move<S>) -> Result<Self, MoveError> {
+        let mut position = self.clone_for_search();
+        let result = position.make_move(m);
+        position.settle_move(result)?;
+        Ok(position)
+    }
+
+    /// Returns each legal move paired with resulting position.
+    fn children(&self, c: Color) -> Vec<(Move<S>, Self)> {
+        self.all_moves(c)
+            .into_iter()
+            .filter_map(|m| self.probe(m.clone()).ok().map(|p| (m, p)))
+            .collect()
+    }
+
+    /// Count leaf nodes of legal move tree for side to move.
+    ///
+    /// Every depth counts moves from `all_moves`, so each promotion piece
+    /// is a separate leaf.
+    fn perft(&self, depth: usize) -> u64 {
+        match depth {
+            0 => 1,
+            _ => self
+                .children(self.side_to_move())
+                .iter()
+                .map(|(_, child)| child.perft(depth - 1))
+                .sum(),
+        }
+    }
+
+    /// Returns `perft` count for each root move.
+    fn perft_divide(&self, depth: usize) -> HashMap<Move<S>, u64> {
+        let mut map = HashMap::new();
+        if depth == 0 {
+            return map;
+        }
+        for (m, child) in self.children(self.side_to_move()) {
+            map.insert(m, child.perft(depth - 1));
+        }
+        map
+    }
+
+    /// Returns material of White minus material of Black.
+    ///
+    /// Piece types missing from `piece_values` use `PieceType::price`.
+    fn material_balance(&self, piece_values: &HashMap<PieceType, i32>) -> i32 {
+        let mut balance = 0;
+        for pt in PieceType::iter() {
+            let value =
+                piece_values.get(&pt).copied().unwrap_or_else(|| pt.price());
+            let bb = self.type_bb(&pt);
+            let white = (bb & &self.player_bb(Color::White)).len() as i32;
+            let black = (bb & &self.player_bb(Color::Black)).len() as i32;
+            balance += (white - black) * value;
+        }
+        balance
+    }
+
+    /// Returns change in mover's legal move count after move is played.
+    fn mobility_delta(&self, m: &Move<S>) -> Result<i32, MoveError> {
+        let color = self.side_to_move();
+        let before = self.legal_move_count(color) as i32;
+        let after = self.probe(m.clone())?.legal_move_count(color) as i32;
+        Ok(after - before)
+    }
+
+    /// Returns number of squares where king can escape.
+    fn king_escape_count(&self, c: Color) -> u32 {
+        self.king_moves(c).len() as u32
+    }
+
+    /// Returns pieces of color attacking square, with given blockers.
+    fn attackers_to(&self, to: S, color: Color, blockers: B) -> B {
+        let mut attackers = B::empty();
+        for piece_type in PieceType::iter() {
+            let pieces = self.type_bb(&piece_type) & &self.player_bb(color);
+            if piece_type == PieceType::Plinth || pieces.is_empty() {
+                continue;
+            }
+            // Pawns attack backwards like enemy pawns, other pieces attack
+            // the same way in both directions.
+            let (piece, blockers) = match piece_type {
+                PieceType::Pawn => (
+                    Piece {
+                        piece_type,
+                        color: color.flip(),
+                    },
+                    !B::empty(),
+                ),
+                _ => (Piece { piece_type, color }, blockers),
+            };
+            attackers |= &(self.get_moves(&to, &piece, blockers) & &pieces);
+        }
+        attackers
+    }
+
+    /// Returns static exchange value of `side` capturing on `to`.
+    /// Exchange starts with least valuable attacker of `side`.
+    fn see(&self, to: S, side: Color) -> i32 {
+        self.see_from(to, side, None)
+    }
+
+    /// Returns static exchange value of playing `m`. Unlike `see`, piece
+    /// moved by `m` is first one to capture on target square.
+    fn see_move(&self, m: &Move<S>) -> i32 {
+        match m.info() {
+            Some((from, to)) => match self.piece_at(from) {
+                Some(p) => self.see_from(to, p.color, Some(from)),
+                None => 0,
+            },
+            None => 0,
+        }
+    }
+
+    /// Returns static exchange value of `side` capturing on `to`, with piece
+    /// on `first` as first capturer. If `first` is `None`, exchange starts
+    /// with least valuable attacker and empty or own `to` is worth 0.
+    fn see_from(&self, to: S, side: Color, first: Option<S>) -> i32 {
+        let value = |p: &Piece| match p.piece_type {
+            PieceType::King => 1000,
+            pt => pt.price(),
+        };
+        let mut captured = match (self.piece_at(to), first) {
+            (Some(p), _) if p.color == side.flip() => value(p),
+            (None, Some(_)) => 0,
+            _ => return 0,
+        };
+        let mut blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        let mut removed = B::empty();
+        let mut color = side;
+        let mut gains = Vec::new();
+        loop {
+            // Attackers are searched again after each capture, so sliders
+            // behind removed pieces join the exchange.
+            let attacker = match first.filter(|_| gains.is_empty()) {
+                Some(sq) => self.piece_at(sq).map(|p| (sq, value(&p))),
+                None => (self.attackers_to(to, color, blockers) & &!removed)
+                    .into_iter()
+                    .filter_map(|sq| self.piece_at(sq).map(|p| (sq, value(&p))))
+                    .min_by_key(|(_, v)| *v),
+            };
+            match attacker {
+                Some((sq, v)) => {
+                    gains.push(captured);
+                    captured = v;
+                    blockers &= &!B::from_square(&sq);
+                    removed |= &sq;
+                    color = color.flip();
+                }
+                None => break,
+            }
+        }
+        if gains.is_empty() {
+            return 0;
+        }
+        let mut score = 0;
+        for gain in gains.iter().skip(1).rev() {
+            score = (gain - score).max(0);
+        }
+        gains[0] - score
+    }
+
+    /// Heuristic zugzwang check. Every legal move of side to move either
+    /// reduces king escape squares or loses moved piece by `see`.
+    fn all_moves_worsen(&self) -> bool {
+        let color = self.side_to_move();
+        let escapes = self.king_escape_count(color);
+        let moves = self.all_moves(color);
+        if moves.is_empty() {
+            return false;
+        }
+        moves.into_iter().all(|m| {
+            let to = match m.info() {
+                Some((_, to)) => to,
+                None => return true,
+            };
+            match self.probe(m) {
+                Ok(child) => {
+                    child.king_escape_count(color) < escapes
+                        || child.see(to, color.flip()) > 0
+                }
+                Err(_) => true,
+            }
+        })
+    }
+
+    /// Returns legal moves without those where moved piece is lost by `see`.
+    fn non_hanging_moves(&self, c: Color) -> HashMap<S, B> {
+        let mut map = HashMap::new();
+        for (from, targets) in self.legal_moves(&c) {
+            let mut safe = B::empty();
+            for to in targets {
+                let m = Move::new(from, to);
+                let keep = if self.is_capture(&m) {
+                    self.see_move(&m) >= 0
+                } else {
+                    match self.probe(m) {
+                        Ok(child) => child.see(to, c.flip()) <= 0,
+                        Err(_) => false,
+                    }
+                };
+                if keep {
+                    safe |= &to;
+                }
+            }
+            map.insert(from, safe);
+        }
+        map
+    }
+
+    /// Play random legal moves until game is over or `max_plies` is reached.
+    fn random_game(&mut self, seed: u64, max_plies: u16) -> Vec<Move<S>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut moves = Vec::new();
+        while moves.len() < max_plies as usize {
+            let legal = self.all_moves(self.side_to_move());
+            let m = match legal.choose(&mut rng) {
+                Some(m) => m.clone(),
+                None => break,
+            };
+            let result = self.make_move(m.clone());
+            if self.settle_move(result).is_err() {
+                break;
+            }
+            moves.push(m);
+            match self.outcome() {
+                Outcome::MoveOk | Outcome::Check { .. } => (),
+                _ => break,
+            }
+        }
+        moves
+    }
+
+    /// If last position has appeared three times then it's draw.
+    fn detect_repetition(&self) -> Result<(), MoveError> {
+        // Positions before last capture, pawn move or castling can not repeat.
+        let window = self.halfmove_clock() as usize + 1;
+        let mut keys = Vec::new();
+        for m in self.move_history().iter().rev().take(window) {
+            match m {
+                Move::Normal { fen, .. } => {
+                    keys.push(fen.split_whitespace().take(2).join(" "))
+                }
+                _ => break,
+            }
+        }
+        if let Some(current) = keys.first() {
+            if keys.iter().filter(|key| *key == current).count() >= 3 {
+                return Err(MoveError::RepetitionDraw);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if only two kings are on board, plinths are ignored.
+    fn only_kings(&self) -> bool {
+        let pieces = self.occupied_bb() & &!self.player_bb(Color::NoColor);
+        let kings = pieces & &self.type_bb(&PieceType::King);
+        kings.len() == 2 && (pieces & &!kings).is_empty()
+    }
+
+    /// If hundred plies have passed without capture or pawn move then it's draw.
+    fn detect_fifty_moves(&self) -> Result<(), MoveError> {
+        if self.halfmove_clock() >= 100 {
+            return Err(MoveError::FiftyMoves);
+        }
+        Ok(())
+    }
+
+    /// Check if one of the players don't have enough pieces.
+    fn detect_insufficient_material(&self) -> Result<(), MoveError> {
+        if self.occupied_bb().len() == 2 {
+            return Err(MoveError::DrawByInsufficientMaterial);
+        }
+        if [Color::White, Color::Black]
+            .iter()
+            .any(|c| self.has_mating_material(*c))
+        {
+            return Ok(());
+        }
+        Err(MoveError::DrawByInsufficientMaterial)
+    }
+
+    /// Check if pieces of `color` could ever deliver mate.
+    fn has_mating_material(&self, c: Color) -> bool {
+        let major = [
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::Chancellor,
+            PieceType::ArchBishop,
+        ];
+        let minor = [PieceType::Knight, PieceType::Bishop, PieceType::Giraffe];
+        let mut bb = B::empty();
+        for i in major {
+            bb |= &(self.player_bb(c) & &self.type_bb(&i));
+        }
+        if bb.is_any() {
+            return true;
+        }
+        for i in minor {
+            bb |= &(self.player_bb(c) & &self.type_bb(&i));
+        }
+        let bishops = self.player_bb(c) & &self.type_bb(&PieceType::Bishop);
+        let mut shades = bishops.map(|sq| (sq.file() + sq.rank()) % 2);
+        let same_shade = match shades.next() {
+            Some(first) => shades.all(|shade| shade == first),
+            None => false,
+        };
+        // Bishops on same square color can never cover the other half.
+        let minor_count = if same_shade && bishops.len() == bb.len() {
+            1
+        } else {
+            bb.len()
+        };
+        if minor_count >= 3 {
+            return true;
+        }
+        (self.player_bb(c) & &self.type_bb(&PieceType::Pawn)).any(|pawn| {
+            let file = self.file_bb(pawn.file() as usize);
+            let mut file_with_plinths = file & &self.player_bb(Color::NoColor);
+            match c {
+                Color::White => file_with_plinths
+                    .pop_reverse()
+                    .is_none_or(|sq| sq.index() <= pawn.index()),
+                _ => file_with_plinths
+                    .pop()
+                    .is_none_or(|sq| sq.index() >= pawn.index()),
+            }
+        })
+    }
+
+    /// Returns all legal moves where piece can be moved.
+    fn legal_moves(&self, color: &Color) -> HashMap<S, B> {
+        let mut map = HashMap::new();
+        let king = self.find_king(color).unwrap();
+        let pinned_moves = self.pins(color);
+        let move_task = self.move_task(color, king);
+        for sq in self.player_bb(*color) {
+            let moves = self.legal_targets(&sq, king, &pinned_moves, move_task);
+            map.insert(sq, moves);
+        }
+        map
+    }
+
+    /// Returns all legal moves, sorted by square index.
+    fn legal_moves_sorted(&self, color: Color) -> Vec<(S, B)> {
+        let mut moves: Vec<(S, B)> =
+            self.legal_moves(&color).into_iter().collect();
+        moves.sort_by_key(|(sq, _)| sq.index());
+        moves
+    }
+
+    /// Returns legal moves that capture enemy piece.
+    /// Pieces without captures are left out.
+    fn legal_captures(&self, color: Color) -> HashMap<S, B> {
+        let mut map = HashMap::new();
+        let king = match self.find_king(&color) {
+            Some(king) => king,
+            None => return map,
+        };
+        let enemy = self.player_bb(color.flip());
+        let pinned_moves = self.pins(&color);
+        let move_task = self.move_task(&color, king);
+        for sq in self.player_bb(color) {
+            let captures =
+                self.legal_targets(&sq, king, &pinned_moves, move_task)
+                    & &enemy;
+            if captures.is_any() {
+                map.insert(sq, captures);
             }
-            Err(error) => match error {
-                MoveError::RepetitionDraw => {
-                    self.update_outcome(Outcome::DrawByRepetition)
-                }
-                MoveError::Draw => self.update_outcome(Outcome::Draw),
-                MoveError::DrawByInsufficientMaterial => {
-                    self.update_outcome(Outcome::DrawByMaterial)
-                }
-                MoveError::DrawByStalemate => {
-                    self.update_outcome(Outcome::Stalemate)
-                }
-                _ => {
-                    return Err(SfenError::IllegalMove);
-                }
-            },
         }
-        return Ok(self.outcome());
+        map
     }
 
-    /// If last position has appeared three times then it's draw.
-    fn detect_repetition(&self) -> Result<(), MoveError> {
-        let sfen_history = self.move_history();
-        let mut h = Vec::new();
-        for i in sfen_history {
-            if let Move::Normal { fen, .. } = i {
-                h.push(fen);
-                if h.len() > 10 {
-                    break;
+    /// Returns legal targets for piece on square, if it belongs to side to move.
+    fn legal_moves_from(&self, sq: &S) -> B {
+        let color = self.side_to_move();
+        match self.piece_at(*sq) {
+            Some(piece) if piece.color == color => (),
+            _ => return B::empty(),
+        }
+        let king = match self.find_king(&color) {
+            Some(king) => king,
+            None => return B::empty(),
+        };
+        let pinned_moves = self.pins(&color);
+        let move_task = self.move_task(&color, king);
+        self.legal_targets(sq, king, &pinned_moves, move_task)
+    }
+
+    /// Returns squares attacked by piece on square, except squares with
+    /// its own pieces. Pins, checks and side to move are ignored.
+    fn attacks_from(&self, sq: &S) -> B {
+        let piece = match self.piece_at(*sq) {
+            Some(piece) => piece,
+            None => return B::empty(),
+        };
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        self.piece_attacks(*sq, blockers) & &!self.player_bb(piece.color)
+    }
+
+    /// Returns checks together with squares attacked by enemy.
+    /// Checkers are searched only if enemy attacks the king.
+    fn move_task(&self, color: &Color, king: S) -> Checks<S, B> {
+        let enemy_moves = self.enemy_moves(color);
+        let check_moves = if (enemy_moves & &king).is_any() {
+            self.check_moves(*color)
+        } else {
+            Checks::new(None, false, None)
+        };
+        check_moves.add_enemy_moves(enemy_moves).unwrap()
+    }
+
+    /// Returns legal targets for one piece, with pins and checks computed.
+    fn legal_targets(
+        &self,
+        sq: &S,
+        king: S,
+        pinned_moves: &HashMap<S, B>,
+        move_task: Checks<S, B>,
+    ) -> B {
+        // Kings can not be captured, even when position leaves one in check.
+        let my_moves =
+            self.non_legal_moves(sq) & &!self.type_bb(&PieceType::King);
+        if king != *sq {
+            return self.fix_pin(sq, pinned_moves, move_task, my_moves);
+        }
+        let mut moves = if move_task.check.is_some() {
+            my_moves & &!move_task.enemy_moves.unwrap_or_else(B::empty)
+        } else {
+            self.fix_pin(sq, pinned_moves, move_task, my_moves)
+        };
+        if self.castling_rights().iter().any(|r| *r) {
+            let rank = king.rank();
+            let enemy = self.side_to_move().flip();
+            let enemy_attacks = self.attack_maps()[enemy.index()];
+            for file in [2, 6] {
+                let to = match S::new(file, rank) {
+                    Some(to) => to,
+                    None => continue,
+                };
+                if let Some(castle) = self.castling_move(king, to) {
+                    if self.can_castle_against(&castle, enemy_attacks) {
+                        moves |= &to;
+                    }
                 }
             }
         }
-        let sfen_history: Vec<&&String> = h.iter().rev().take(15).collect();
-        let cur = sfen_history.last().unwrap();
-        let last_sfen = cur.split_whitespace().rev().last().unwrap();
-        let mut cnt = 0;
-        for entry in sfen_history.iter().rev() {
-            let s = entry.split_whitespace().rev().last().unwrap();
-            if s == last_sfen {
-                cnt += 1;
-                if cnt == 3 {
-                    return Err(MoveError::RepetitionDraw);
+        moves
+    }
+
+    /// Returns all legal moves as list, sorted by squares.
+    /// Pawn moves to last rank are expanded to every allowed promotion.
+    fn all_moves(&self, c: Color) -> Vec<Move<S>> {
+        let mut moves = Vec::new();
+        let pawns = self.player_bb(c) & &self.type_bb(&PieceType::Pawn);
+        for (from, targets) in self.legal_moves(&c) {
+            for to in targets {
+                if (pawns & &from).is_any() && to.in_promotion_zone(c) {
+                    for pt in PieceType::iter()
+                        .filter(|pt| self.variant().can_promote_to(pt))
+                    {
+                        moves.push(Move::new_promotion(from, to, pt));
+                    }
+                } else {
+                    moves.push(Move::new(from, to));
                 }
             }
         }
-        Ok(())
+        moves.sort_by_key(|m| {
+            m.info().map(|(from, to)| (from.index(), to.index()))
+        });
+        moves
     }
 
-    /// Check if one of the players don't have enough pieces.
-    fn detect_insufficient_material(&self) -> Result<(), MoveError> {
-        let major = [
-            PieceType::Rook,
-            PieceType::Queen,
-            PieceType::Chancellor,
-            PieceType::ArchBishop,
-        ];
-        let minor = [PieceType::Knight, PieceType::Bishop, PieceType::Giraffe];
-        if self.occupied_bb().len() == 2 {
-            return Err(MoveError::DrawByInsufficientMaterial);
+    /// Returns all squares where any piece of color can legally move.
+    fn reachable(&self, c: Color) -> B {
+        let mut bb = B::empty();
+        for moves in self.legal_moves(&c).values() {
+            bb |= moves;
         }
-        for c in Color::iter() {
-            if c == Color::NoColor {
-                continue;
-            }
-            let mut bb = B::empty();
-            for i in major {
-                bb |= &(self.player_bb(c) & &self.type_bb(&i));
-            }
-            if bb.is_any() {
-                return Ok(());
-            }
-            for i in minor {
-                bb |= &(self.player_bb(c) & &self.type_bb(&i));
-            }
-            let minor_count = bb.len();
-            if minor_count >= 3 {
-                return Ok(());
+        bb
+    }
+
+    /// Returns all legal king moves.
+    fn king_moves(&self, c: Color) -> Vec<Move<S>> {
+        let king = match self.find_king(&c) {
+            Some(king) => king,
+            None => return Vec::new(),
+        };
+        match self.legal_moves(&c).get(&king) {
+            Some(moves) => {
+                moves.into_iter().map(|to| Move::new(king, to)).collect()
             }
-            for pawn in self.player_bb(c) & &self.type_bb(&PieceType::Pawn) {
-                let file = pawn.file();
-                let file = self.file_bb(file as usize);
-                let mut file_with_plinths =
-                    file & &self.player_bb(Color::NoColor);
-                if file_with_plinths.is_empty() {
-                    return Ok(());
-                } else if c == Color::White {
-                    if let Some(sq) = file_with_plinths.pop_reverse() {
-                        if sq.index() <= pawn.index() {
-                            bb |= &pawn;
-                            // return Ok(());
-                        }
-                    }
-                    continue;
-                } else if c == Color::Black {
-                    if let Some(sq) = file_with_plinths.pop() {
-                        if sq.index() >= pawn.index() {
-                            bb |= &pawn;
-                            // return Ok(());
-                        }
-                    }
-                    continue;
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns all legal pawn captures which are promoting, one for each
+    /// piece type pawn can promote to.
+    fn promotion_captures(&self, c: Color) -> Vec<Move<S>> {
+        let enemy = self.player_bb(c.flip());
+        let pawns = self.player_bb(c) & &self.type_bb(&PieceType::Pawn);
+        self.all_moves(c)
+            .into_iter()
+            .filter(|m| match m.info() {
+                Some((from, to)) => {
+                    (pawns & &from).is_any()
+                        && (enemy & &to).is_any()
+                        && to.in_promotion_zone(c)
                 }
-            }
-            if bb.len() == 0 || minor_count == bb.len() {
-                continue;
-            }
+                None => false,
+            })
+            .collect()
+    }
 
-            return Ok(());
+    /// Returns number of legal moves.
+    fn legal_move_count(&self, c: Color) -> usize {
+        self.legal_moves(&c)
+            .values()
+            .map(|moves| moves.len() as usize)
+            .sum()
+    }
+
+    /// Returns number of legal moves for each piece.
+    fn move_counts(&self, c: Color) -> HashMap<S, u32> {
+        self.legal_moves(&c)
+            .into_iter()
+            .map(|(sq, moves)| (sq, moves.len()))
+            .collect()
+    }
+
+    /// Returns all legal moves as if piece on `removed` square is gone.
+    fn legal_moves_without(&self, c: Color, removed: S) -> HashMap<S, B> {
+        let mut position = self.clone();
+        if let Some(piece) = *position.piece_at(removed) {
+            position.set_piece(removed, None);
+            position.xor_player_bb(piece.color, removed);
+            position.xor_type_bb(piece.piece_type, removed);
+            position.xor_occupied(removed);
         }
-        Err(MoveError::DrawByInsufficientMaterial)
+        position.legal_moves(&c)
     }
 
-    /// Returns all legal moves where piece can be moved.
-    fn legal_moves(&self, color: &Color) -> HashMap<S, B> {
+    /// Returns all pseudo-legal moves, without pin and check filtering.
+    fn pseudo_legal_moves(&self, color: Color) -> HashMap<S, B> {
         let mut map = HashMap::new();
-        let pinned_moves = self.pins(color);
-        let check_moves = self.check_moves(*color);
-        let enemy_moves = self.enemy_moves(color);
-        let move_task = check_moves.add_enemy_moves(enemy_moves).unwrap();
-        let king = self.find_king(color).unwrap();
-        for sq in self.player_bb(*color) {
-            let my_moves = self.non_legal_moves(&sq);
-            if check_moves.check.is_some() {
-                if king == sq {
-                    map.insert(king, my_moves & &!enemy_moves);
-                } else {
-                    let moves =
-                        self.fix_pin(&sq, &pinned_moves, move_task, my_moves);
-                    map.insert(sq, moves);
-                }
-            } else {
-                let moves =
-                    self.fix_pin(&sq, &pinned_moves, move_task, my_moves);
-                map.insert(sq, moves);
-            }
+        for sq in self.player_bb(color) {
+            map.insert(sq, self.non_legal_moves(&sq));
         }
         map
     }
@@ -905,6 +2419,44 @@ where
             Color::NoColor => B::empty(),
         }
     }
+    /// Returns attacks from square in single direction, stopped by blockers.
+    fn ray_attack(&self, sq: S, dir: Ray) -> B {
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        match dir {
+            Ray::North | Ray::East | Ray::NorthEast | Ray::NorthWest => {
+                A::get_positive_ray_attacks(dir, sq.index(), blockers)
+            }
+            Ray::South | Ray::West | Ray::SouthEast | Ray::SouthWest => {
+                A::get_negative_ray_attacks(dir, sq.index(), blockers)
+            }
+        }
+    }
+
+    /// Check if move captures enemy piece. There is no en passant.
+    fn is_capture(&self, m: &Move<S>) -> bool {
+        match m.info() {
+            Some((from, to)) => {
+                match (self.piece_at(from), self.piece_at(to)) {
+                    (Some(moved), Some(target)) => {
+                        target.color == moved.color.flip()
+                    }
+                    _ => false,
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// Check if squares are aligned and squares between them are empty.
+    fn clear_line(&self, a: S, b: S) -> bool {
+        let aligned = A::get_sliding_attacks(PieceType::Queen, &a, B::empty());
+        if (aligned & &b).is_empty() {
+            return false;
+        }
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        (A::between(a, b) & &blockers).is_empty()
+    }
+
     /// Returns all non-legal moves.
     fn non_legal_moves(&self, square: &S) -> B {
         let piece = self.piece_at(*square);
@@ -971,6 +2523,14 @@ where
         bb
     }
 
+    /// Set position from SFEN string.
+    ///
+    /// # Errors
+    ///
+    /// Set variant before calling this. Hand is validated against current
+    /// variant, so SFEN with fairy pieces in hand returns
+    /// `SfenError::InvalidHand` until `update_variant` is called with fairy
+    /// variant.
     fn set_sfen(&mut self, sfen_str: &str) -> Result<Outcome, SfenError> {
         let mut parts = sfen_str.split_whitespace();
         parts
@@ -985,10 +2545,13 @@ where
             .next()
             .ok_or(SfenError::MissingDataFields)
             .and_then(|s| self.parse_sfen_hand(s))?;
+        self.validate_board()?;
         parts
             .next()
             .ok_or(SfenError::MissingDataFields)
             .and_then(|s| self.parse_sfen_ply(s))?;
+        self.parse_sfen_castling(parts.next().unwrap_or("-"))?;
+        self.update_halfmove_clock(parts.next().unwrap_or("0").parse()?);
         self.clear_sfen_history();
         self.log_position();
         if self.in_check(self.side_to_move().flip()) {
@@ -1001,6 +2564,13 @@ where
         Ok(Outcome::Nothing)
     }
 
+    /// Parse sfen and return it in canonical form.
+    fn normalize_sfen(sfen: &str) -> Result<String, SfenError> {
+        let mut position = Self::new();
+        position.set_sfen(sfen)?;
+        Ok(position.generate_sfen())
+    }
+
     fn in_check(&self, c: Color) -> bool {
         let king = &self.find_king(&c);
         if let Some(k) = king {
@@ -1015,31 +2585,60 @@ where
         false
     }
 
-    /// Checks if given color is in checkmate.
-    fn is_checkmate(&self, c: &Color) -> bool {
-        let king = self.find_king(c);
-        match king {
-            Some(k) => {
-                if !self.in_check(*c) {
-                    return false;
+    /// Returns all enemy pieces giving check to king of `color`.
+    fn checkers(&self, color: Color) -> B {
+        let mut checkers = B::empty();
+        if let Some(king) = self.find_king(&color) {
+            let occupied_bb =
+                self.occupied_bb() | &self.player_bb(Color::NoColor);
+            for pt in PieceType::iter() {
+                if pt == PieceType::King || !self.variant().can_buy(&pt) {
+                    continue;
                 }
-                let all = self.legal_moves(c);
-                if let Some(king_moves) = all.get(&k) {
-                    if !king_moves.is_any() {
-                        let mut final_moves = B::empty();
-                        for mv in all {
-                            final_moves |= &mv.1;
-                        }
-                        if final_moves.is_any() {
-                            return false;
-                        }
-                        return true;
-                    }
+                let (_, attackers) =
+                    self.piece_checks(&king, pt, color, occupied_bb);
+                checkers |= &attackers;
+            }
+        }
+        checkers
+    }
+
+    /// Returns pieces of color that would give discovered check to enemy
+    /// king by moving away from the line of own slider.
+    fn discovered_check_candidates(&self, color: Color) -> B {
+        let mut candidates = B::empty();
+        let ksq = match self.find_king(&color.flip()) {
+            Some(ksq) => ksq,
+            None => return candidates,
+        };
+        let plinths = self.player_bb(Color::NoColor);
+        for pt in [
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::Chancellor,
+            PieceType::ArchBishop,
+        ] {
+            if !self.variant().can_buy(&pt) {
+                continue;
+            }
+            let attacks = A::get_sliding_attacks(pt, &ksq, plinths);
+            let sliders =
+                (self.type_bb(&pt) & &self.player_bb(color)) & &attacks;
+            for slider in sliders {
+                let blockers =
+                    (A::between(ksq, slider) & &self.occupied_bb()) & &!plinths;
+                if blockers.len() == 1 {
+                    candidates |= &(blockers & &self.player_bb(color));
                 }
-                false
             }
-            None => false,
         }
+        candidates
+    }
+
+    /// Checks if given color is in checkmate.
+    fn is_checkmate(&self, c: &Color) -> bool {
+        self.in_check(*c) && !self.has_any_legal_move(*c)
     }
 
     fn gen_move_data(
@@ -1069,15 +2668,38 @@ where
 
     /// Check if player is in stalemate.
     fn is_stalemate(&self, color: &Color) -> Result<(), MoveError> {
-        let moves = self.legal_moves(color);
-        for m in moves {
-            if m.1.len() > 0 {
-                return Ok(());
-            }
+        if self.has_any_legal_move(*color) {
+            return Ok(());
         }
         Err(MoveError::DrawByStalemate)
     }
 
+    /// Returns `Check`, `Checkmate`, `Stalemate` or `MoveOk` for color.
+    fn status(&self, color: Color) -> Outcome {
+        match (self.in_check(color), self.has_any_legal_move(color)) {
+            (true, true) => Outcome::Check { color },
+            (true, false) => Outcome::Checkmate {
+                color: color.flip(),
+            },
+            (false, false) => Outcome::Stalemate,
+            (false, true) => Outcome::MoveOk,
+        }
+    }
+
+    /// Check if color has at least one legal move, stopping at first one.
+    fn has_any_legal_move(&self, color: Color) -> bool {
+        let king = match self.find_king(&color) {
+            Some(king) => king,
+            None => return false,
+        };
+        let pinned_moves = self.pins(&color);
+        let move_task = self.move_task(&color, king);
+        self.player_bb(color).any(|sq| {
+            self.legal_targets(&sq, king, &pinned_moves, move_task)
+                .is_any()
+        })
+    }
+
     /// Returns a `BitBoard` where the given piece at the given square can move.
     fn move_candidates(
         &self,
@@ -1109,27 +2731,230 @@ where
 
     fn game_status(&self) -> Outcome;
 
+    /// Returns castling move if king moves two squares from its
+    /// starting square and side still has that castling right.
+    fn castling_move(&self, from: S, to: S) -> Option<Move<S>> {
+        let king = (*self.piece_at(from))?;
+        if king.piece_type != PieceType::King || from.rank() != to.rank() {
+            return None;
+        }
+        let (rank, index) = match king.color {
+            Color::White => (0, 0),
+            Color::Black => (self.dimensions() - 1, 2),
+            Color::NoColor => return None,
+        };
+        let last = self.dimensions() - 1;
+        let (king_to, rook_from, rook_to, index) = match to.file() {
+            6 => (6, last, 5, index),
+            2 => (2, 0, 3, index + 1),
+            _ => return None,
+        };
+        if from.file() != 4
+            || from.rank() != rank
+            || to.file() != king_to
+            || !self.castling_rights()[index]
+        {
+            return None;
+        }
+        Some(Move::Castle {
+            king_from: from,
+            king_to: to,
+            rook_from: S::new(rook_from, rank)?,
+            rook_to: S::new(rook_to, rank)?,
+            move_data: MoveData::default(),
+        })
+    }
+
+    /// Check if castling is legal in current position.
+    fn can_castle(&self, m: &Move<S>) -> bool {
+        let enemy = self.side_to_move().flip();
+        self.can_castle_against(m, self.attack_maps()[enemy.index()])
+    }
+
+    /// Check if castling is legal, with squares attacked by enemy given.
+    fn can_castle_against(&self, m: &Move<S>, enemy_attacks: B) -> bool {
+        let (king_from, king_to, rook_from, rook_to) = match m {
+            Move::Castle {
+                king_from,
+                king_to,
+                rook_from,
+                rook_to,
+                ..
+            } => (*king_from, *king_to, *rook_from, *rook_to),
+            _ => return false,
+        };
+        match self.castling_move(king_from, king_to) {
+            Some(Move::Castle {
+                rook_from: from,
+                rook_to: to,
+                ..
+            }) if from == rook_from && to == rook_to => (),
+            _ => return false,
+        }
+        let color = match self.piece_at(king_from) {
+            Some(king) => king.color,
+            None => return false,
+        };
+        let rook = Piece {
+            piece_type: PieceType::Rook,
+            color,
+        };
+        if color != self.side_to_move()
+            || *self.piece_at(rook_from) != Some(rook)
+            || self.in_check(color)
+        {
+            return false;
+        }
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        if (A::between(king_from, rook_from) & &blockers).is_any() {
+            return false;
+        }
+        let path = A::between(king_from, king_to) | &king_to;
+        (path & &enemy_attacks).is_empty()
+    }
+
+    /// Play castling move.
+    fn make_castle(&mut self, m: Move<S>) -> Result<Outcome, MoveError> {
+        if !self.can_castle(&m) {
+            if let Move::Castle {
+                king_from, king_to, ..
+            } = m
+            {
+                return Err(MoveError::illegal(
+                    king_from,
+                    king_to,
+                    "Castling is not allowed",
+                ));
+            }
+            return Err(MoveError::Inconsistent("Castling is not allowed"));
+        }
+        if let Move::Castle {
+            king_from,
+            king_to,
+            rook_from,
+            rook_to,
+            ..
+        } = m
+        {
+            let color = self.side_to_move();
+            let move_data = MoveData::default()
+                .previous(self.castling_rights(), self.halfmove_clock());
+            for (from, to, piece_type) in [
+                (king_from, king_to, PieceType::King),
+                (rook_from, rook_to, PieceType::Rook),
+            ] {
+                self.move_piece(from, to, Piece { piece_type, color });
+            }
+            self.update_castling_after_move(king_from, king_to);
+            self.update_halfmove_clock(self.halfmove_clock() + 1);
+            self.flip_side_to_move();
+            self.increment_ply();
+            if self.record_history() {
+                self.insert_move(Move::Castle {
+                    king_from,
+                    king_to,
+                    rook_from,
+                    rook_to,
+                    move_data,
+                });
+            }
+            let opponent = color.flip();
+            if self.is_checkmate(&opponent) {
+                return Ok(Outcome::Checkmate { color });
+            }
+            self.detect_fifty_moves()?;
+            if self.in_check(opponent) {
+                return Ok(Outcome::Check { color: opponent });
+            }
+            self.is_stalemate(&opponent)?;
+        }
+        Ok(Outcome::MoveOk)
+    }
+
+    /// Move piece to empty square.
+    fn move_piece(&mut self, from: S, to: S, piece: Piece) {
+        self.set_piece(from, None);
+        self.set_piece(to, Some(piece));
+        for sq in [from, to] {
+            self.xor_player_bb(piece.color, sq);
+            self.xor_type_bb(piece.piece_type, sq);
+            self.xor_occupied(sq);
+        }
+    }
+
+    /// Remove castling rights if king or rook has moved or rook is captured.
+    fn update_castling_after_move(&mut self, from: S, to: S) {
+        let mut rights = self.castling_rights();
+        if !rights.iter().any(|r| *r) {
+            return;
+        }
+        let last = self.dimensions() - 1;
+        for sq in [from, to] {
+            let offset = match sq.rank() {
+                0 => 0,
+                r if r == last => 2,
+                _ => continue,
+            };
+            match sq.file() {
+                4 => {
+                    rights[offset] = false;
+                    rights[offset + 1] = false;
+                }
+                0 => rights[offset + 1] = false,
+                f if f == last => rights[offset] = false,
+                _ => (),
+            }
+        }
+        self.update_castling_rights(rights);
+    }
+
     /// Make move from `Move`. It can be of three types.
     /// It's useful for all three stages of the game.
     fn make_move(&mut self, m: Move<S>) -> Result<Outcome, MoveError> {
+        if self.is_game_ended() {
+            return Err(MoveError::GameEnded);
+        }
+        match (&m, self.game_stage()) {
+            (
+                Move::Normal { .. } | Move::Castle { .. },
+                GameStage::Deployment,
+            ) => {
+                return Err(MoveError::NotInFight);
+            }
+            (Move::Put { .. }, GameStage::Fight) => {
+                return Err(MoveError::NotInDeployment);
+            }
+            _ => (),
+        }
+        let castle = match m {
+            Move::Castle { .. } => Some(m.clone()),
+            _ => m.info().and_then(|(from, to)| self.castling_move(from, to)),
+        };
+        if let Some(castle) = castle {
+            return self.make_castle(castle);
+        }
         let mut promoted = false;
         let stm = self.side_to_move();
         let opponent = stm.flip();
         if let Some((from, to)) = m.info() {
             //
-            let moved = self
-                .piece_at(from)
-                .ok_or(MoveError::Inconsistent("No piece found"))?;
+            let moved = self.piece_at(from).ok_or(MoveError::illegal(
+                from,
+                to,
+                "No piece found",
+            ))?;
             let captured = *self.piece_at(to);
             let outcome = Outcome::Checkmate { color: opponent };
             let legal_moves = self.legal_moves(&stm);
 
             if moved.color != stm {
-                return Err(MoveError::Inconsistent(
+                return Err(MoveError::illegal(
+                    from,
+                    to,
                     "The piece is not for the side to move",
                 ));
             } else if self.game_status() == outcome {
-                return Err(MoveError::Inconsistent("Match is over."));
+                return Err(MoveError::illegal(from, to, "Match is over."));
             }
 
             match captured {
@@ -1149,23 +2974,36 @@ where
                 }
             }
 
-            if let Some(attacks) = legal_moves.get(&from) {
-                if (*attacks & &to).is_empty() {
-                    return Err(MoveError::Inconsistent(
-                        "The piece cannot move to there",
-                    ));
-                }
-            } else {
-                return Err(MoveError::Inconsistent(
+            let reachable = legal_moves
+                .get(&from)
+                .is_some_and(|attacks| (*attacks & &to).is_any());
+            if !reachable {
+                return Err(MoveError::illegal(
+                    from,
+                    to,
                     "The piece cannot move to there",
                 ));
             }
-            let mut move_data = MoveData::default();
+            let mut move_data = MoveData::default()
+                .previous(self.castling_rights(), self.halfmove_clock());
 
             let placed = if promoted {
-                match moved.promote() {
-                    Some(promoted) => promoted,
-                    None => {
+                match (m.promotion(), moved.promote()) {
+                    (Some(piece_type), Some(_)) => {
+                        if !self.variant().can_promote_to(&piece_type) {
+                            return Err(MoveError::illegal(
+                                from,
+                                to,
+                                "Pawn cannot promote to this piece",
+                            ));
+                        }
+                        Piece {
+                            piece_type,
+                            color: moved.color,
+                        }
+                    }
+                    (None, Some(promoted)) => promoted,
+                    (_, None) => {
                         return Err(MoveError::Inconsistent(
                             "This type of piece cannot promote",
                         ));
@@ -1177,6 +3015,12 @@ where
 
             move_data = move_data.promoted(promoted);
             move_data = move_data.piece(Some(moved));
+            self.update_castling_after_move(from, to);
+            if moved.piece_type == PieceType::Pawn || captured.is_some() {
+                self.update_halfmove_clock(0);
+            } else {
+                self.update_halfmove_clock(self.halfmove_clock() + 1);
+            }
 
             move_data = self.update_after_move(
                 from, to, placed, moved, captured, opponent, move_data,
@@ -1191,33 +3035,40 @@ where
                 } else if self.in_check(stm) {
                     move_data = move_data.checks(true, false);
                     Outcome::Check { color: stm }
-                } else if (self.player_bb(stm.flip())
-                    & &self.type_bb(&PieceType::King))
-                    .len()
-                    == 0
-                {
-                    move_data = move_data.checks(false, true);
-                    Outcome::Checkmate { color: stm.flip() }
                 } else {
                     Outcome::MoveOk
                 }
             };
 
-            move_data =
-                self.gen_move_data(&legal_moves, (from, to), moved, move_data);
-            let move_record = Move::Normal {
-                from,
-                to,
-                placed,
-                move_data,
-                fen: String::new(),
-            };
-
-            self.insert_move(move_record);
-
-            self.log_position();
-            self.detect_repetition()?;
-            self.detect_insufficient_material()?;
+            if self.record_history() {
+                move_data = self.gen_move_data(
+                    &legal_moves,
+                    (from, to),
+                    moved,
+                    move_data,
+                );
+                let move_record = Move::Normal {
+                    from,
+                    to,
+                    placed,
+                    promotion: promoted.then_some(placed.piece_type),
+                    move_data,
+                    fen: String::new(),
+                };
+
+                self.insert_move(move_record);
+
+                self.log_position();
+                if self.auto_draw_repetition() {
+                    self.detect_repetition()?;
+                }
+            }
+            if self.auto_draw_material() {
+                self.detect_insufficient_material()?;
+            }
+            if !matches!(outcome, Outcome::Checkmate { .. }) {
+                self.detect_fifty_moves()?;
+            }
 
             if outcome == Outcome::MoveOk {
                 self.is_stalemate(&stm)?;
@@ -1429,6 +3280,85 @@ where
         }
     }
 
+    /// Returns squares attacked by Black and White, indexed by color.
+    fn attack_maps(&self) -> [B; 2] {
+        [
+            self.attacked_by(Color::Black),
+            self.attacked_by(Color::White),
+        ]
+    }
+
+    /// Returns all squares attacked by pieces of color. Unlike
+    /// `enemy_moves`, every king stays on board as blocker.
+    fn attacked_by(&self, color: Color) -> B {
+        let mut attacks = B::empty();
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        for sq in self.player_bb(color) {
+            attacks |= &self.piece_attacks(sq, blockers);
+        }
+        attacks
+    }
+
+    /// Returns squares attacked by piece on square.
+    fn piece_attacks(&self, sq: S, blockers: B) -> B {
+        match self.piece_at(sq) {
+            Some(piece) if piece.piece_type == PieceType::Pawn => {
+                self.get_moves(&sq, piece, !B::empty())
+            }
+            Some(piece) => self.get_moves(&sq, piece, blockers),
+            None => B::empty(),
+        }
+    }
+
+    /// Returns number of friendly pieces attacking squares around the king.
+    fn king_defenders(&self, c: Color) -> u32 {
+        let king = match self.find_king(&c) {
+            Some(king) => king,
+            None => return 0,
+        };
+        let ring =
+            A::get_non_sliding_attacks(PieceType::King, &king, c, B::empty());
+        let blockers = self.occupied_bb() | &self.player_bb(Color::NoColor);
+        let mut defenders = 0;
+        for sq in self.player_bb(c) {
+            if sq != king && (self.piece_attacks(sq, blockers) & &ring).is_any()
+            {
+                defenders += 1;
+            }
+        }
+        defenders
+    }
+
+    /// Returns difference between empty squares attacked by White and Black.
+    fn space_control(&self) -> i32 {
+        let empty = !(self.occupied_bb() | &self.player_bb(Color::NoColor));
+        let maps = self.attack_maps();
+        let white = (maps[Color::White.index()] & &empty).len() as i32;
+        let black = (maps[Color::Black.index()] & &empty).len() as i32;
+        white - black
+    }
+
+    /// Returns moves of `pt` from king square and enemy pieces of that
+    /// type found on them.
+    fn piece_checks(
+        &self,
+        king: &S,
+        pt: PieceType,
+        attacked_color: Color,
+        occupied_bb: B,
+    ) -> (B, B) {
+        let moves = self.get_moves(
+            king,
+            &Piece {
+                piece_type: pt,
+                color: attacked_color,
+            },
+            occupied_bb,
+        );
+        let them = self.type_bb(&pt) & &self.player_bb(attacked_color.flip());
+        (moves, them & &moves)
+    }
+
     fn check_moves(&self, attacked_color: Color) -> Checks<S, B> {
         let mut king =
             self.type_bb(&PieceType::King) & &self.player_bb(attacked_color);
@@ -1448,17 +3378,12 @@ where
                     if !self.variant().can_buy(&pt) {
                         continue;
                     }
-                    let moves = self.get_moves(
+                    let (moves, mut attackers) = self.piece_checks(
                         &king,
-                        &Piece {
-                            piece_type: pt,
-                            color: attacked_color,
-                        },
+                        pt,
+                        attacked_color,
                         occupied_bb,
                     );
-                    let them = self.type_bb(&pt)
-                        & &self.player_bb(attacked_color.flip());
-                    let mut attackers = (them & &moves) & &them;
                     let len = attackers.len();
                     match &len.cmp(&1) {
                         Ordering::Equal => {
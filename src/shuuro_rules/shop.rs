@@ -1,16 +1,15 @@
 use crate::shuuro_rules::Square;
-use std::u8;
 
-use crate::shuuro_rules::{variant::Variant, Color, Piece, PieceType};
+use crate::shuuro_rules::{
+    variant::Variant, Color, Piece, PieceType, SfenError,
+};
 use crate::shuuro_rules::{Hand, Move};
 
 fn get_pricing() -> [(i32, u8); 10] {
-    let prices = [0, 110, 70, 40, 40, 10, 130, 130, 70, 0];
-    let count = [1, 3, 6, 9, 9, 18, 3, 3, 4, 0];
     let mut pricing: [(i32, u8); 10] = [(0, 0); 10];
     let pt_iter = PieceType::iter();
     for pt in pt_iter {
-        pricing[pt.index()] = (prices[pt.index()], count[pt.index()]);
+        pricing[pt.index()] = (pt.price(), pt.max_count());
     }
     pricing
 }
@@ -24,6 +23,7 @@ pub struct Shop<S: Square> {
     pricing: [(i32, u8); 10],
     move_history: Vec<Move<S>>,
     sfen_history: Vec<(String, u8)>,
+    selections: [Vec<PieceType>; 2],
     variant: Variant,
 }
 
@@ -38,10 +38,9 @@ impl<S: Square> Shop<S> {
 
     /// Update pricing
     fn update_pricing(&mut self) {
-        if self.variant == Variant::Standard
-            || self.variant == Variant::StandardFairy
-        {
-            self.pricing[PieceType::Pawn.index()] = (10, 12);
+        for pt in PieceType::iter() {
+            self.pricing[pt.index()] =
+                (pt.price(), self.variant.max_count(&pt));
         }
     }
 
@@ -49,43 +48,72 @@ impl<S: Square> Shop<S> {
         self.variant
     }
 
+    /// Check if color can buy one more piece. Piece must be allowed in
+    /// variant and fit in remaining credit. Count of each piece is capped
+    /// by `Variant::max_count`.
+    pub fn can_buy(&self, piece: Piece) -> bool {
+        let (piece_price, piece_count) = self.pricing[piece.piece_type.index()];
+        self.variant.can_buy(&piece.piece_type)
+            && piece.color != Color::NoColor
+            && !self.is_confirmed(piece.color)
+            && self.credit(piece.color) >= piece_price
+            && self.hand.get(piece) < piece_count
+    }
+
     /// Buying piece with specific color.
     pub fn play(&mut self, mv: Move<S>) -> Option<[bool; 2]> {
-        if let Move::Buy { piece } = mv {
-            if !self.variant.can_buy(&piece.piece_type)
-                || piece.color == Color::NoColor
-            {
-                return None;
-            } else if !self.is_confirmed(piece.color) {
-                let (piece_price, piece_count) =
-                    self.pricing[piece.piece_type.index()];
-                if self.credit[piece.color.index()] >= piece_price {
-                    let current_hand = self.hand.get(piece);
-                    match current_hand.cmp(&piece_count) {
-                        std::cmp::Ordering::Less => {
-                            self.hand.increment(piece);
-                            self.credit[piece.color.index()] =
-                                self.credit(piece.color) - piece_price;
-                            let move_record = Move::Buy { piece };
-                            self.sfen_history.push((
-                                move_record.to_string(),
-                                self.hand.get(piece),
-                            ));
-                            self.move_history.push(move_record);
-                            if self.credit[piece.color.index()] == 0 {
-                                self.confirm(piece.color);
-                            }
-                            return Some(self.confirmed);
-                        }
-                        std::cmp::Ordering::Equal => {
-                            return None;
-                        }
-                        std::cmp::Ordering::Greater => return None,
-                    }
-                }
-            }
+        let piece = match mv {
+            Move::Buy { piece } if self.can_buy(piece) => piece,
+            _ => return None,
+        };
+        let piece_price = self.pricing[piece.piece_type.index()].0;
+        self.hand.increment(piece);
+        self.credit[piece.color.index()] =
+            self.credit(piece.color) - piece_price;
+        let move_record = Move::Buy { piece };
+        self.sfen_history
+            .push((move_record.to_string(), self.hand.get(piece)));
+        self.move_history.push(move_record);
+        self.selections[piece.color.index()].push(piece.piece_type);
+        if self.credit[piece.color.index()] == 0 {
+            self.confirm(piece.color);
         }
-        None
+        Some(self.confirmed)
+    }
+
+    /// Returns all bought piece types by color, in order of buying.
+    pub fn selection_history(&self, color: Color) -> &[PieceType] {
+        self.selections
+            .get(color.index())
+            .map_or(&[], |selections| selections.as_slice())
+    }
+
+    /// Takes back last bought piece and refunds its price. Kings and
+    /// confirmed selections can not be undone.
+    pub fn undo_select(&mut self, color: Color) -> Option<PieceType> {
+        if color == Color::NoColor || self.is_confirmed(color) {
+            return None;
+        }
+        let selections = &mut self.selections[color.index()];
+        let piece_type = *selections.last()?;
+        if piece_type == PieceType::King {
+            return None;
+        }
+        selections.pop();
+        let piece = Piece { piece_type, color };
+        self.hand.decrement(piece);
+        self.credit[color.index()] += self.pricing[piece_type.index()].0;
+        let move_record = Move::<S>::Buy { piece };
+        if let Some(i) =
+            self.move_history.iter().rposition(|m| m == &move_record)
+        {
+            self.move_history.remove(i);
+        }
+        let record = move_record.to_string();
+        if let Some(i) = self.sfen_history.iter().rposition(|m| m.0 == record) {
+            self.sfen_history.remove(i);
+        }
+        Some(piece_type)
     }
 
     /// Confirm your choice of pieces.
@@ -122,6 +150,7 @@ impl<S: Square> Shop<S> {
             }
         }
         self.hand = Hand::default();
+        self.selections = Default::default();
         for m in selected {
             self.play(m);
         }
@@ -141,6 +170,91 @@ impl<S: Square> Shop<S> {
         self.credit[c.index()]
     }
 
+    /// Returns buyable piece types with how many more can be bought.
+    pub fn affordable(&self, color: Color) -> Vec<(PieceType, u8)> {
+        let mut pieces = vec![];
+        for piece_type in PieceType::iter() {
+            if piece_type == PieceType::King
+                || !self.variant.can_buy(&piece_type)
+            {
+                continue;
+            }
+            let (price, cap) = self.pricing[piece_type.index()];
+            let piece = Piece { piece_type, color };
+            let mut count = cap.saturating_sub(self.hand.get(piece));
+            if self.is_confirmed(color) {
+                count = 0;
+            } else if price > 0 {
+                let by_credit = (self.credit(color) / price).min(255) as u8;
+                count = count.min(by_credit);
+            }
+            pieces.push((piece_type, count));
+        }
+        pieces
+    }
+
+    /// Exports variant and hands of both players, separated by spaces.
+    pub fn export(&self) -> String {
+        let hand = |c: Color| match self.to_sfen(c, false) {
+            s if s.is_empty() => String::from("-"),
+            s => s,
+        };
+        format!(
+            "{} {} {}",
+            self.variant,
+            hand(Color::White),
+            hand(Color::Black)
+        )
+    }
+
+    /// Replaces current selection with one from `export`, buying every
+    /// piece again so credit limits are checked.
+    pub fn import(&mut self, s: &str) -> Result<(), SfenError> {
+        let mut parts = s.split_whitespace();
+        let variant: Variant =
+            parts.next().ok_or(SfenError::MissingDataFields)?.parse()?;
+        let mut selected = vec![];
+        for color in [Color::White, Color::Black] {
+            let hand = parts.next().ok_or(SfenError::MissingDataFields)?;
+            if hand == "-" {
+                continue;
+            }
+            let is_own = |ch: char| {
+                Piece::from_sfen(ch).is_some_and(|p| {
+                    p.color == color && p.piece_type != PieceType::Plinth
+                })
+            };
+            if !hand.chars().all(|ch| ch.is_numeric() || is_own(ch)) {
+                return Err(SfenError::IllegalPieceType);
+            }
+            let hand = Hand::from(hand);
+            for piece_type in PieceType::iter() {
+                let piece = Piece { piece_type, color };
+                for _ in 0..hand.get(piece) {
+                    selected.push(piece);
+                }
+            }
+        }
+        if parts.next().is_some() {
+            return Err(SfenError::TooManyDataFields);
+        }
+        let mut shop = Shop::<S> {
+            hand: Hand::default(),
+            move_history: Vec::new(),
+            sfen_history: Vec::new(),
+            selections: Default::default(),
+            ..Shop::default()
+        };
+        shop.update_variant(variant);
+        for piece in selected {
+            if shop.play(Move::Buy { piece }).is_none() {
+                return Err(SfenError::IllegalSelection);
+            }
+        }
+        *self = shop;
+        Ok(())
+    }
+
     /// Checks if color is confirmed it's choice.
     pub fn is_confirmed(&self, c: Color) -> bool {
         self.confirmed[c.index()]
@@ -185,6 +299,7 @@ impl<S: Square> Default for Shop<S> {
             pricing: get_pricing(),
             move_history: Default::default(),
             sfen_history: Default::default(),
+            selections: Default::default(),
             variant: Variant::Shuuro,
         };
         shop.set_kings();
@@ -9,7 +9,12 @@ where
 {
     fn new(file: u8, rank: u8) -> Option<Self>;
     fn from_sfen(s: &str) -> Option<Self>;
+    /// Returns `None` if index is outside of board.
     fn from_index(index: u8) -> Option<Self>;
+    /// Returns all squares of board, ordered by index.
+    fn all() -> impl Iterator<Item = Self> {
+        (0..=u8::MAX).map_while(Self::from_index)
+    }
     fn right_edge(&self) -> u8;
     fn up_edge(&self) -> u8;
     fn to_int(&self) -> u8;
@@ -24,4 +29,10 @@ where
     fn rank(&self) -> u8;
     fn file(&self) -> u8;
     fn index(&self) -> usize;
+    /// Returns square `df` files and `dr` ranks away, `None` if off board.
+    fn offset(&self, df: i8, dr: i8) -> Option<Self> {
+        let file = u8::try_from(self.file() as i16 + df as i16).ok()?;
+        let rank = u8::try_from(self.rank() as i16 + dr as i16).ok()?;
+        Self::new(file, rank)
+    }
 }
@@ -1,6 +1,9 @@
-use crate::shuuro_rules::PieceType;
+use std::{fmt, str::FromStr};
 
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+use crate::shuuro_rules::{PieceType, SfenError};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub enum Variant {
     Shuuro,
     ShuuroFairy,
@@ -22,6 +25,23 @@ impl From<&String> for Variant {
     }
 }
 
+impl FromStr for Variant {
+    type Err = SfenError;
+
+    /// Parses variant name as written by `Display`. Unlike `From<&String>`,
+    /// unknown names are an error.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shuuro" => Ok(Self::Shuuro),
+            "shuuroFairy" => Ok(Self::ShuuroFairy),
+            "shuuroMini" => Ok(Self::ShuuroMini),
+            "standard" => Ok(Self::Standard),
+            "standardFairy" => Ok(Self::StandardFairy),
+            _ => Err(SfenError::UnknownVariant),
+        }
+    }
+}
+
 impl Variant {
     pub fn change_variant(&self, variant: &String) -> Self {
         Variant::from(variant)
@@ -37,6 +57,20 @@ impl Variant {
         }
     }
 
+    /// Check if pawn can promote to this piece in this variant.
+    pub fn can_promote_to(&self, piece: &PieceType) -> bool {
+        !matches!(piece, PieceType::King | PieceType::Pawn)
+            && self.can_buy(piece)
+    }
+
+    /// Returns how many pieces of this type can be bought in this variant.
+    pub fn max_count(&self, piece: &PieceType) -> u8 {
+        match (self, piece) {
+            (Self::Standard | Self::StandardFairy, PieceType::Pawn) => 12,
+            _ => piece.max_count(),
+        }
+    }
+
     pub fn start_credit(&self) -> i32 {
         match &self {
             Self::Shuuro => 800,
@@ -48,14 +82,14 @@ impl Variant {
     }
 }
 
-impl ToString for Variant {
-    fn to_string(&self) -> String {
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            Self::Shuuro => String::from("shuuro"),
-            Self::ShuuroFairy => String::from("shuuroFairy"),
-            Self::ShuuroMini => String::from("shuuroMini"),
-            Self::Standard => String::from("standard"),
-            Self::StandardFairy => String::from("standardFairy"),
+            Self::Shuuro => write!(f, "shuuro"),
+            Self::ShuuroFairy => write!(f, "shuuroFairy"),
+            Self::ShuuroMini => write!(f, "shuuroMini"),
+            Self::Standard => write!(f, "standard"),
+            Self::StandardFairy => write!(f, "standardFairy"),
         }
     }
 }
@@ -0,0 +1,74 @@
+use std::sync::Once;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{Color, Piece, Variant};
+
+const SQUARES: usize = 144;
+const PIECES: usize = 30;
+
+static mut PIECE_KEYS: [[u64; SQUARES]; PIECES] = [[0; SQUARES]; PIECES];
+static mut SIDE_KEY: u64 = 0;
+static mut CASTLING_KEYS: [u64; 4] = [0; 4];
+static mut HAND_KEYS: [u64; PIECES] = [0; PIECES];
+static mut VARIANT_KEYS: [u64; 5] = [0; 5];
+
+static INIT: Once = Once::new();
+
+/// Fills zobrist key tables. Keys are same on every run and only change if
+/// `rand` changes its `StdRng` algorithm.
+pub fn init() {
+    INIT.call_once(|| {
+        let mut rng = StdRng::seed_from_u64(0x5348_5555_524f);
+        let mut pieces = [[0; SQUARES]; PIECES];
+        for key in pieces.iter_mut().flatten() {
+            *key = rng.gen();
+        }
+        let side = rng.gen();
+        let castling = rng.gen();
+        let hand = rng.gen();
+        let variant = rng.gen();
+        unsafe {
+            PIECE_KEYS = pieces;
+            SIDE_KEY = side;
+            CASTLING_KEYS = castling;
+            HAND_KEYS = hand;
+            VARIANT_KEYS = variant;
+        }
+    });
+}
+
+/// Returns key for piece on square index.
+pub fn piece_key(p: Piece, sq: usize) -> u64 {
+    let index = p.piece_type.index() * 3 + p.color.index();
+    unsafe { PIECE_KEYS[index][sq] }
+}
+
+/// Returns key for `count` pieces in hand.
+pub fn hand_key(p: Piece, count: u8) -> u64 {
+    let index = p.piece_type.index() * 3 + p.color.index();
+    unsafe { HAND_KEYS[index] }.wrapping_mul(count as u64)
+}
+
+/// Returns key for variant.
+pub fn variant_key(variant: Variant) -> u64 {
+    unsafe { VARIANT_KEYS[variant as usize] }
+}
+
+/// Returns key for side to move.
+pub fn side_key(c: Color) -> u64 {
+    match c {
+        Color::Black => unsafe { SIDE_KEY },
+        _ => 0,
+    }
+}
+
+/// Returns key for all castling rights.
+pub fn castling_key(rights: [bool; 4]) -> u64 {
+    rights
+        .iter()
+        .enumerate()
+        .filter(|(_, right)| **right)
+        .map(|(i, _)| unsafe { CASTLING_KEYS[i] })
+        .fold(0, |key, k| key ^ k)
+}